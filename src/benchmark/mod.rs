@@ -0,0 +1,129 @@
+use std::{fs, io, path::{Path, PathBuf}, process, sync::Arc, time::{Duration, Instant}};
+
+use tokio::{sync::mpsc, task::JoinSet};
+
+use crate::{config::Config, file_helper::{get_files_path, get_size, size, WorkerPool, CountedFiles, ErrorLog, CancelToken}};
+
+const GROUPS: usize = 10;
+const PACKAGES: usize = 20;
+const PACKAGE_FILES: usize = 5;
+const FILE_SIZE: usize = 512;
+
+struct GeneratedTree {
+    root: PathBuf,
+    projects: usize,
+    dirs: usize,
+    files: usize,
+}
+
+impl GeneratedTree {
+    /// Directories the search walks: groups, projects and their `src`, never `node_modules`.
+    fn searched_dirs(&self) -> usize {
+        self.projects.min(GROUPS) + self.projects * 2
+    }
+}
+
+impl Drop for GeneratedTree {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.root);
+    }
+}
+
+fn write_file(path: &Path, tree: &mut GeneratedTree) -> io::Result<()> {
+    fs::write(path, vec![b'x'; FILE_SIZE])?;
+    tree.files += 1;
+    Ok(())
+}
+
+fn create_dir(path: &Path, tree: &mut GeneratedTree) -> io::Result<()> {
+    fs::create_dir_all(path)?;
+    tree.dirs += 1;
+    Ok(())
+}
+
+/// `projects` Node projects spread over a few groups, each with its own `node_modules`.
+fn generate_tree(projects: usize) -> io::Result<GeneratedTree> {
+    let root = std::env::temp_dir().join(format!("rust-kill-modules-bench-{}", process::id()));
+    let mut tree = GeneratedTree { root, projects, dirs: 0, files: 0 };
+
+    for project in 0..projects {
+        let project_dir = tree.root.join(format!("group-{}", project % GROUPS)).join(format!("project-{}", project));
+        create_dir(&project_dir.join("src"), &mut tree)?;
+        write_file(&project_dir.join("package.json"), &mut tree)?;
+        write_file(&project_dir.join("src").join("index.js"), &mut tree)?;
+
+        for package in 0..PACKAGES {
+            let package_dir = project_dir.join("node_modules").join(format!("package-{}", package));
+            create_dir(&package_dir.join("lib"), &mut tree)?;
+            write_file(&package_dir.join("package.json"), &mut tree)?;
+            for file in 0..PACKAGE_FILES {
+                write_file(&package_dir.join("lib").join(format!("file-{}.js", file)), &mut tree)?;
+            }
+        }
+    }
+
+    Ok(tree)
+}
+
+fn per_second(count: usize, duration: Duration) -> f64 {
+    count as f64 / duration.as_secs_f64().max(f64::EPSILON)
+}
+
+async fn measure(config: &Config, tree: &GeneratedTree, threads: usize) {
+    let pool = WorkerPool::new(threads, config.open_dirs.unwrap_or(threads));
+    let mut option = config.search_option();
+    option.roots = vec![tree.root.clone()];
+    let option = Arc::new(option);
+
+    let started = Instant::now();
+    let (tx, mut rx) = mpsc::channel(100);
+    tokio::spawn(get_files_path(pool.clone(), option.clone(), CancelToken::default(), None, ErrorLog::default(), tx));
+    let mut targets = vec![];
+    while let Some(target) = rx.recv().await {
+        targets.push(target.entry.path());
+    }
+    let search_time = started.elapsed();
+
+    let started = Instant::now();
+    let mut set = JoinSet::new();
+    for target in targets.iter().cloned() {
+        set.spawn(get_size(pool.clone(), target, option.clone(), CountedFiles::default(), ErrorLog::default(), CancelToken::default(), |_| {}));
+    }
+    let mut total = 0u128;
+    while let Some(result) = set.join_next().await {
+        total += u128::from(result.map(|size| size.apparent).unwrap_or(0));
+    }
+    let size_time = started.elapsed();
+
+    println!(
+        "{:>7} {:>9} {:>12.0} {:>12.0} {:>12}",
+        threads,
+        targets.len(),
+        per_second(tree.searched_dirs(), search_time),
+        per_second(tree.files, size_time),
+        size(total),
+    );
+}
+
+/// Generate a tree of `projects` projects and time the search and size calculation on it with
+/// an increasing number of threads.
+pub async fn run(config: &Config, projects: usize) {
+    let tree = match generate_tree(projects) {
+        Ok(tree) => tree,
+        Err(err) => {
+            println!("Cannot generate benchmark tree: {}", err);
+            return;
+        }
+    };
+
+    println!("Generated {} directories and {} files in {}", tree.dirs, tree.files, tree.root.display());
+    println!("{:>7} {:>9} {:>12} {:>12} {:>12}", "threads", "targets", "search dir/s", "size file/s", "total");
+
+    let max_threads = config.threads();
+    let mut threads = 1;
+    while threads < max_threads {
+        measure(config, &tree, threads).await;
+        threads *= 2;
+    }
+    measure(config, &tree, max_threads).await;
+}
@@ -0,0 +1,376 @@
+use std::{env, fs, path::{Path, PathBuf}, thread, time::Duration};
+
+use crate::file_helper::{SearchOption, TargetProfile, TargetProfiles, SkippedMounts, WorkerPool, SizeCache, read_mounts, manifest_file};
+
+const APP_NAME: &str = "rust-kill-modules";
+const CONFIG_FILE: &str = "config";
+const SIZE_CACHE_FILE: &str = "sizes";
+
+const DEFAULT_AGE_THRESHOLD: Duration = Duration::from_secs(30 * 24 * 60 * 60);
+
+const USAGE: &str = r"Usage: rust-kill-modules [OPTIONS] [PATH]...
+
+Arguments:
+  [PATH]...                   Directories to search (default: current directory)
+
+Options:
+  -a, --hidden                Include hidden directories in the search
+  -L, --follow-links          Search into symlinked directories (cycles are skipped)
+  -x, --one-file-system       Do not search into directories on other filesystems
+      --allow-fs <TYPES>      Search into pseudo, network and FUSE mounts of the given
+                              filesystem types (comma separated, e.g. `nfs4,fuse.sshfs`),
+                              or `all` to never skip a mount
+  -w, --watch                 Keep watching searched directories for target directories being
+                              created or removed (Linux only)
+  -d, --max-depth <DEPTH>     Only search directories up to DEPTH levels below each root
+  -e, --exclude <GLOB>        Skip directories matching GLOB (gitignore syntax, repeatable)
+  -j, --threads <N>           Number of threads reading the filesystem (default: CPU count)
+      --open-dirs <N>         Maximum number of directories open at once (default: threads)
+  -o, --older-than <AGE>      Only show projects idle for at least AGE (e.g. `30days`, `6months`);
+                              'o' toggles this filter in the UI, using 30 days by default
+      --mark-idle             Mark the projects idle for at least AGE (30 days by default)
+                              as soon as their age is known; 'O' does it in the UI
+  -p, --profile <NAMES>       Only search for the given profiles (comma separated)
+      --define <NAME:DIRS[:MANIFESTS]>
+                              Define a profile matching the given directory names
+                              (comma separated). When manifests are given, one of
+                              them must exist next to the directory (or inside it
+                              when prefixed with `/`), e.g. `--define elm:elm-stuff:elm.json`
+  -n, --dry-run               Go through deletions without deleting anything
+  -t, --trash                 Move deleted directories to the trash (freedesktop.org
+                              specification) instead of removing them
+      --no-confirm            Delete without asking for confirmation first
+      --no-cache              Do not read or write the size cache
+      --clear-cache           Empty the size cache before searching (with --dry-run,
+                              only ignore it)
+      --duplicates            Print the package versions installed in several node_modules,
+                              with the space a shared store would save, and exit
+      --benchmark <PROJECTS>  Generate a tree of PROJECTS projects in the temp directory,
+                              print search and sizing throughput per thread count and exit
+  -h, --help                  Print this help
+
+Deleted directories are renamed aside and only removed when the UI is closed, 'u' puts
+them back until then. Those left behind by a session that was killed are removed on the
+next start.
+
+Built-in profiles: node, rust, python, pycache, gradle, next, dist, vendor, pods
+
+A `.rkmignore` file (gitignore syntax) excludes directories below the folder it is in.
+
+Sizes are cached in $XDG_CACHE_HOME/rust-kill-modules/sizes, shown right away on the next
+run and refreshed in the background.
+
+Options can also be written one per line in the config file
+($XDG_CONFIG_HOME/rust-kill-modules/config) without the leading dashes,
+e.g. `define = elm:elm-stuff:elm.json`.";
+
+pub enum ConfigError {
+    Help,
+    Invalid(String),
+    MissingValue(String),
+    InvalidValue(String, String),
+    InvalidPath(String),
+}
+
+impl ConfigError {
+    pub fn message(&self) -> String {
+        let error = match self {
+            ConfigError::Help => return USAGE.to_owned(),
+            ConfigError::Invalid(arg) => format!("Unknown argument: {}", arg),
+            ConfigError::MissingValue(arg) => format!("Missing value for: {}", arg),
+            ConfigError::InvalidValue(arg, value) => format!("Invalid value for {}: {}", arg, value),
+            ConfigError::InvalidPath(path) => return format!("Cannot access directory: {}", path),
+        };
+
+        format!("{}\n\n{}", error, USAGE)
+    }
+}
+
+#[derive(Default)]
+pub struct Config {
+    pub roots: Vec<PathBuf>,
+    pub include_hidden: bool,
+    pub follow_links: bool,
+    pub one_file_system: bool,
+    pub watch: bool,
+    pub allowed_fs: Vec<String>,
+    pub max_depth: Option<usize>,
+    pub excludes: Vec<String>,
+    pub threads: Option<usize>,
+    pub open_dirs: Option<usize>,
+    pub trash: bool,
+    pub dry_run: bool,
+    pub no_confirm: bool,
+    pub no_cache: bool,
+    pub clear_cache: bool,
+    pub benchmark: Option<usize>,
+    pub duplicates: bool,
+    pub older_than: Option<Duration>,
+    pub mark_idle: bool,
+    pub profile_names: Option<Vec<String>>,
+    pub custom_profiles: Vec<TargetProfile>,
+}
+
+fn next_value(args: &mut impl Iterator<Item = String>, arg: &str) -> Result<String, ConfigError> {
+    args.next().ok_or_else(|| ConfigError::MissingValue(arg.to_owned()))
+}
+
+fn parse_count(arg: String, value: String) -> Result<usize, ConfigError> {
+    match value.parse() {
+        Ok(count) if count > 0 => Ok(count),
+        _ => Err(ConfigError::InvalidValue(arg, value)),
+    }
+}
+
+fn split_list(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(|item| item.trim().to_owned())
+        .filter(|item| !item.is_empty())
+        .collect()
+}
+
+fn home_dir() -> Option<PathBuf> {
+    env::var_os("HOME").or_else(|| env::var_os("USERPROFILE")).map(PathBuf::from)
+}
+
+/// Expand a leading `~` to the home directory and resolve the path to an absolute one.
+fn resolve_root(value: &str) -> Result<PathBuf, ConfigError> {
+    let path = match value.strip_prefix('~') {
+        Some(rest) if rest.is_empty() || rest.starts_with('/') || rest.starts_with('\\') => {
+            let home = home_dir().ok_or_else(|| ConfigError::InvalidPath(value.to_owned()))?;
+            home.join(rest.trim_start_matches(['/', '\\']))
+        },
+        _ => PathBuf::from(value),
+    };
+
+    match fs::canonicalize(&path) {
+        Ok(path) if path.is_dir() => Ok(path),
+        _ => Err(ConfigError::InvalidPath(value.to_owned())),
+    }
+}
+
+pub fn cache_dir() -> Option<PathBuf> {
+    let base = match env::var_os("XDG_CACHE_HOME") {
+        Some(dir) => PathBuf::from(dir),
+        None if cfg!(windows) => PathBuf::from(env::var_os("LOCALAPPDATA")?),
+        None => home_dir()?.join(".cache"),
+    };
+
+    Some(base.join(APP_NAME))
+}
+
+pub fn config_dir() -> Option<PathBuf> {
+    let base = match env::var_os("XDG_CONFIG_HOME") {
+        Some(dir) => PathBuf::from(dir),
+        None if cfg!(windows) => PathBuf::from(env::var_os("APPDATA")?),
+        None => home_dir()?.join(".config"),
+    };
+
+    Some(base.join(APP_NAME))
+}
+
+/// Turn `key = value` / `key` lines of the config file into `--key value` / `--key` arguments.
+fn read_config_file() -> Vec<String> {
+    let content = match config_dir().map(|dir| fs::read_to_string(dir.join(CONFIG_FILE))) {
+        Some(Ok(content)) => content,
+        _ => return vec![],
+    };
+
+    let mut args = vec![];
+    for line in content.lines().map(str::trim) {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        match line.split_once('=') {
+            Some((key, value)) => {
+                args.push(format!("--{}", key.trim()));
+                args.push(value.trim().to_owned());
+            },
+            None => args.push(format!("--{}", line)),
+        }
+    }
+
+    args
+}
+
+impl Config {
+    pub fn from_args() -> Result<Config, ConfigError> {
+        let mut config = Config::default();
+
+        config.apply(read_config_file())?;
+        config.apply(env::args().skip(1))?;
+        config.check_profile_names()?;
+
+        if config.roots.is_empty() {
+            let current_directory = env::current_dir()
+                .map_err(|_| ConfigError::InvalidPath(Path::new(".").display().to_string()))?;
+            config.roots.push(current_directory);
+        }
+
+        Ok(config)
+    }
+
+    fn apply(&mut self, args: impl IntoIterator<Item = String>) -> Result<(), ConfigError> {
+        let mut args = args.into_iter();
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "-a" | "--hidden" => self.include_hidden = true,
+                "-L" | "--follow-links" => self.follow_links = true,
+                "-x" | "--one-file-system" => self.one_file_system = true,
+                "-w" | "--watch" => self.watch = true,
+                "-t" | "--trash" => self.trash = true,
+                "-n" | "--dry-run" => self.dry_run = true,
+                "--no-confirm" => self.no_confirm = true,
+                "--no-cache" => self.no_cache = true,
+                "--clear-cache" => self.clear_cache = true,
+                "--duplicates" => self.duplicates = true,
+                "--mark-idle" => self.mark_idle = true,
+                "--allow-fs" => {
+                    let value = next_value(&mut args, &arg)?;
+                    self.allowed_fs.extend(split_list(&value));
+                },
+                "-d" | "--max-depth" => {
+                    let value = next_value(&mut args, &arg)?;
+                    // A depth of 0 would not even read the roots.
+                    self.max_depth = Some(parse_count(arg, value)?);
+                },
+                "-e" | "--exclude" => {
+                    let value = next_value(&mut args, &arg)?;
+                    self.excludes.push(value);
+                },
+                "-j" | "--threads" => {
+                    let value = next_value(&mut args, &arg)?;
+                    self.threads = Some(parse_count(arg, value)?);
+                },
+                "--open-dirs" => {
+                    let value = next_value(&mut args, &arg)?;
+                    self.open_dirs = Some(parse_count(arg, value)?);
+                },
+                "--benchmark" => {
+                    let value = next_value(&mut args, &arg)?;
+                    self.benchmark = Some(parse_count(arg, value)?);
+                },
+                "-o" | "--older-than" => {
+                    let value = next_value(&mut args, &arg)?;
+                    match humantime::parse_duration(&value) {
+                        Ok(age) => self.older_than = Some(age),
+                        Err(_) => return Err(ConfigError::InvalidValue(arg, value)),
+                    }
+                },
+                "-p" | "--profile" => {
+                    let value = next_value(&mut args, &arg)?;
+                    self.profile_names = Some(split_list(&value));
+                },
+                "--define" => {
+                    let value = next_value(&mut args, &arg)?;
+                    match TargetProfile::parse(&value) {
+                        Some(profile) => self.custom_profiles.push(profile),
+                        None => return Err(ConfigError::InvalidValue(arg, value)),
+                    }
+                },
+                "-h" | "--help" => return Err(ConfigError::Help),
+                _ if arg.starts_with('-') => return Err(ConfigError::Invalid(arg)),
+                _ => self.roots.push(resolve_root(&arg)?),
+            }
+        }
+
+        Ok(())
+    }
+
+    fn check_profile_names(&self) -> Result<(), ConfigError> {
+        let names = match &self.profile_names {
+            Some(names) => names,
+            None => return Ok(()),
+        };
+
+        let known = self.all_profiles();
+        match names.iter().find(|name| !known.iter().any(|profile| &profile.name == *name)) {
+            Some(name) => Err(ConfigError::InvalidValue("--profile".to_owned(), name.clone())),
+            None => Ok(()),
+        }
+    }
+
+    /// User-defined profiles come first so they can override a built-in profile of the same name.
+    fn all_profiles(&self) -> Vec<TargetProfile> {
+        let mut profiles = self.custom_profiles.clone();
+        for profile in TargetProfile::built_in() {
+            if !profiles.iter().any(|custom| custom.name == profile.name) {
+                profiles.push(profile);
+            }
+        }
+
+        profiles
+    }
+
+    fn profiles(&self) -> TargetProfiles {
+        let mut profiles = self.all_profiles();
+
+        if let Some(names) = &self.profile_names {
+            profiles.retain(|profile| names.contains(&profile.name));
+        }
+
+        TargetProfiles::new(profiles)
+    }
+
+    fn skipped_mounts(&self) -> SkippedMounts {
+        if self.allowed_fs.iter().any(|fs_type| fs_type == "all") {
+            return SkippedMounts::default();
+        }
+
+        SkippedMounts::new(read_mounts(), &self.allowed_fs)
+    }
+
+    pub fn age_threshold(&self) -> Duration {
+        self.older_than.unwrap_or(DEFAULT_AGE_THRESHOLD)
+    }
+
+    /// The size cache to use, or `None` when it is disabled or has nowhere to live.
+    pub fn size_cache(&self) -> Option<SizeCache> {
+        let file = cache_dir()?.join(SIZE_CACHE_FILE);
+        let cache = match (self.clear_cache, self.no_cache) {
+            // A dry run leaves the file alone and only ignores what it holds.
+            (true, _) if self.dry_run => SizeCache::empty(file),
+            (true, _) => SizeCache::clear(file),
+            (false, true) => return None,
+            (false, false) => SizeCache::load(file),
+        };
+
+        match self.no_cache {
+            true => None,
+            false => Some(cache),
+        }
+    }
+
+    /// Where this process writes down what it has in quarantine, next to those of the others.
+    pub fn quarantine_manifest(&self) -> Option<PathBuf> {
+        Some(manifest_file(&cache_dir()?))
+    }
+
+    pub fn threads(&self) -> usize {
+        match self.threads {
+            Some(threads) => threads,
+            None => thread::available_parallelism().map(|count| count.get()).unwrap_or(4),
+        }
+    }
+
+    pub fn worker_pool(&self) -> WorkerPool {
+        let threads = self.threads();
+        WorkerPool::new(threads, self.open_dirs.unwrap_or(threads))
+    }
+
+    pub fn search_option(&self) -> SearchOption {
+        SearchOption {
+            roots: self.roots.clone(),
+            include_hidden: self.include_hidden,
+            follow_links: self.follow_links,
+            one_file_system: self.one_file_system,
+            skipped_mounts: self.skipped_mounts(),
+            max_depth: self.max_depth,
+            excludes: self.excludes.clone(),
+            profiles: self.profiles(),
+        }
+    }
+}
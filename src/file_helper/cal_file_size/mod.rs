@@ -1,47 +1,230 @@
-use std::{fs::DirEntry, io::Error, path::Path};
+use std::{fs::{self, Metadata}, io, path::{Path, PathBuf}, sync::{Arc, atomic::{AtomicU64, Ordering}}, time::Duration};
 
-use tokio::task::JoinSet;
+use super::{VisitedDirs, CountedFiles, WorkerPool, TaskGroup, CancelToken, ErrorLog, SearchOption, device_id};
 
-fn cal_size_spawn(entry: Result<DirEntry, Error>, set: &mut JoinSet<u64>) {
-    set.spawn(async move {
-        let entry = match entry {
-            Ok(data) => data,
-            Err(_) => {
-                return 0;
-            }
-        };
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(250);
+
+const NODE_MODULES: &str = "node_modules";
+const PACKAGE_MANIFEST: &str = "package.json";
+
+/// Size and content of a directory. `apparent` adds up file lengths, counting a hard-linked
+/// file once per link; `reclaimable` adds up allocated blocks, counting a hard-linked file only
+/// in the directory where the last of its links is found, and not at all when a link lies
+/// outside every measured directory.
+/// `packages` counts the packages installed in the `node_modules` directories found inside.
+/// `errors` counts the entries that could not be read, so the other figures are too low.
+#[derive(Clone, Copy, Default)]
+pub struct DirStats {
+    pub apparent: u64,
+    pub reclaimable: u64,
+    pub files: u64,
+    pub packages: u64,
+    pub errors: u64,
+}
+
+fn is_named(path: &Path, name: &str) -> bool {
+    path.file_name().map(|file_name| file_name == name).unwrap_or(false)
+}
+
+/// Whether `dir` is `node_modules/<package>` or `node_modules/@scope/<package>`.
+fn is_package_dir(dir: &Path) -> bool {
+    let parent = match dir.parent() {
+        Some(parent) => parent,
+        None => return false,
+    };
+
+    if is_named(parent, NODE_MODULES) {
+        return true;
+    }
+
+    let is_scope = parent.file_name().and_then(|name| name.to_str()).map(|name| name.starts_with('@')).unwrap_or(false);
+    is_scope && parent.parent().map(|node_modules| is_named(node_modules, NODE_MODULES)).unwrap_or(false)
+}
+
+/// Bytes of disk blocks taken by a file.
+#[cfg(unix)]
+pub fn allocated_size(metadata: &Metadata) -> u64 {
+    use std::os::unix::fs::MetadataExt;
+
+    // `st_blocks` is always counted in 512-byte units.
+    metadata.blocks() * 512
+}
 
-        if entry.path().is_dir() {
-            return get_size(entry.path().as_path()).await;
+#[cfg(not(unix))]
+pub fn allocated_size(metadata: &Metadata) -> u64 {
+    metadata.len()
+}
+
+#[cfg(unix)]
+fn link_count(metadata: &Metadata) -> u64 {
+    use std::os::unix::fs::MetadataExt;
+
+    metadata.nlink()
+}
+
+#[cfg(not(unix))]
+fn link_count(_metadata: &Metadata) -> u64 {
+    1
+}
+
+/// Totals of a walk, shared between its tasks.
+#[derive(Clone)]
+struct SizeCounter {
+    apparent: Arc<AtomicU64>,
+    reclaimable: Arc<AtomicU64>,
+    files: Arc<AtomicU64>,
+    packages: Arc<AtomicU64>,
+    errors: Arc<AtomicU64>,
+    visited: VisitedDirs,
+    counted: CountedFiles,
+    log: ErrorLog,
+    option: Arc<SearchOption>,
+    /// The measured directory.
+    root: Arc<Path>,
+    /// Device of the measured directory, for `-x`.
+    root_device: Option<u64>,
+}
+
+impl SizeCounter {
+    fn new(root: Arc<Path>, option: Arc<SearchOption>, counted: CountedFiles, log: ErrorLog) -> SizeCounter {
+        SizeCounter {
+            apparent: Arc::default(),
+            reclaimable: Arc::default(),
+            files: Arc::default(),
+            packages: Arc::default(),
+            errors: Arc::default(),
+            visited: VisitedDirs::default(),
+            counted,
+            log,
+            option,
+            root,
+            root_device: None,
         }
+    }
 
-        let child_size = match entry.metadata() {
-            Ok(data) => data,
-            Err(_) => {
-                return 0;
-            }
-        };
+    /// Mounts below the measured directory are skipped like the search skips them: deleting
+    /// the directory does not free them.
+    fn is_skipped_mount(&self, path: &Path, metadata: &Metadata) -> bool {
+        let other_device = self.root_device.is_some() && device_id(metadata) != self.root_device;
+        (self.option.one_file_system && other_device) || self.option.skipped_mounts.contains(path)
+    }
 
-        child_size.len()
-    });
+    fn add_file(&self, path: &Path, metadata: &Metadata) {
+        self.apparent.fetch_add(metadata.len(), Ordering::Relaxed);
+        self.files.fetch_add(1, Ordering::Relaxed);
+
+        let links = link_count(metadata);
+        if links <= 1 || self.counted.insert(&self.root, path, metadata, links) {
+            self.reclaimable.fetch_add(allocated_size(metadata), Ordering::Relaxed);
+        }
+    }
+
+    /// Returns `false` when the directory was already entered.
+    fn add_dir(&self, path: &Path, metadata: &Metadata) -> bool {
+        if !self.visited.insert(path, metadata) {
+            return false;
+        }
+
+        self.reclaimable.fetch_add(allocated_size(metadata), Ordering::Relaxed);
+        true
+    }
+
+    fn add_error(&self, path: &Path, error: &io::Error) {
+        self.log.push(path, error);
+        self.errors.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn total(&self) -> DirStats {
+        DirStats {
+            apparent: self.apparent.load(Ordering::Relaxed),
+            reclaimable: self.reclaimable.load(Ordering::Relaxed),
+            files: self.files.load(Ordering::Relaxed),
+            packages: self.packages.load(Ordering::Relaxed),
+            errors: self.errors.load(Ordering::Relaxed),
+        }
+    }
+}
+
+fn cal_size_spawn(path: PathBuf, counter: SizeCounter, group: &TaskGroup) {
+    group.spawn(move |group| get_dir_size(&path, counter, group));
 }
 
-pub async fn get_size(path: &Path) -> u64 {
-    let mut size = 0;
-    let children = match path.read_dir() {
-        Ok(children) => children,
-        Err(_) => return 0,
+/// Add up the files of `path` and hand its subdirectories to the pool.
+fn get_dir_size(path: &Path, counter: SizeCounter, group: &TaskGroup) {
+    let (children, error_count) = {
+        let _permit = group.pool().open_dir();
+        counter.log.read_dir(path)
     };
+    counter.errors.fetch_add(error_count, Ordering::Relaxed);
+
+    if is_package_dir(path) && children.iter().any(|entry| entry.file_name() == PACKAGE_MANIFEST) {
+        counter.packages.fetch_add(1, Ordering::Relaxed);
+    }
+
+    for entry in children {
+        // `DirEntry::metadata` does not traverse symlinks, so a link only counts for itself.
+        let metadata = match entry.metadata() {
+            Ok(data) => data,
+            Err(error) => {
+                counter.add_error(&entry.path(), &error);
+                continue;
+            },
+        };
 
-    let mut set = JoinSet::new();
+        if metadata.is_dir() {
+            if counter.is_skipped_mount(&entry.path(), &metadata) {
+                continue;
+            }
+            if counter.add_dir(&entry.path(), &metadata) {
+                cal_size_spawn(entry.path(), counter.clone(), group);
+            }
+            continue;
+        }
 
-    for entry in children .into_iter(){
-        cal_size_spawn(entry, &mut set);
+        counter.add_file(&entry.path(), &metadata);
     }
+}
+
+/// Size of `path`, leaving out the mounts `option` keeps the search out of. Hard-linked files
+/// only add to the reclaimable size once `counted` has seen all their links, and stay with the
+/// same directory when it is measured again. `progress` gets the partial size every
+/// `PROGRESS_INTERVAL` until the walk is done. Entries that cannot be read are added to `log`.
+pub async fn get_size(pool: WorkerPool, path: PathBuf, option: Arc<SearchOption>, counted: CountedFiles, log: ErrorLog, cancel: CancelToken, progress: impl Fn(DirStats)) -> DirStats {
+    // A directory measured again first takes back the links it saw last time.
+    counted.forget(&path);
+    let mut counter = SizeCounter::new(Arc::from(path.as_path()), option, counted, log);
+    let total = counter.clone();
+
+    let walk = pool.run(cancel, move |group| {
+        let metadata = match fs::symlink_metadata(&path) {
+            Ok(metadata) => metadata,
+            Err(error) => {
+                counter.add_error(&path, &error);
+                return;
+            },
+        };
+
+        if !metadata.is_dir() {
+            counter.add_file(&path, &metadata);
+            return;
+        }
+
+        counter.root_device = device_id(&metadata);
+        counter.add_dir(&path, &metadata);
+        get_dir_size(&path, counter, group);
+    });
+    tokio::pin!(walk);
+
+    let mut interval = tokio::time::interval(PROGRESS_INTERVAL);
+    // The first tick completes right away, there is nothing to report yet.
+    interval.tick().await;
 
-    while let Some(result) = set.join_next().await {
-        size += result.unwrap_or(0);
+    loop {
+        tokio::select! {
+            _ = &mut walk => break,
+            _ = interval.tick() => progress(total.total()),
+        }
     }
 
-    return size;
-}
\ No newline at end of file
+    total.total()
+}
@@ -0,0 +1,17 @@
+use std::sync::{Arc, atomic::{AtomicBool, Ordering}};
+
+/// Shared flag telling searches and size calculations to stop as soon as possible.
+#[derive(Clone, Default)]
+pub struct CancelToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancelToken {
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+}
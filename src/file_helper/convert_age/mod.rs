@@ -0,0 +1,45 @@
+use std::time::{Duration, SystemTime};
+
+use humantime::format_duration;
+
+const MINUTE: u64 = 60;
+const HOUR: u64 = 60 * MINUTE;
+const DAY: u64 = 24 * HOUR;
+// Same lengths humantime uses, so rounded values print as a single unit.
+const MONTH: u64 = 2_630_016;
+const YEAR: u64 = 31_557_600;
+
+/// Time elapsed since `time`, rounded down to its largest unit (e.g. "3months").
+pub fn age(time: SystemTime) -> String {
+    let elapsed = SystemTime::now().duration_since(time).unwrap_or_default().as_secs();
+
+    let unit = [YEAR, MONTH, DAY, HOUR, MINUTE]
+        .into_iter()
+        .find(|unit| elapsed >= *unit)
+        .unwrap_or(1);
+
+    format_duration(Duration::from_secs(elapsed - elapsed % unit)).to_string()
+}
+
+/// `duration` in the largest unit dividing it evenly (e.g. "60days" rather than "1month 29days 13h..").
+pub fn duration(duration: Duration) -> String {
+    let seconds = duration.as_secs();
+
+    match [YEAR, MONTH, DAY, HOUR, MINUTE].into_iter().find(|unit| seconds >= *unit && seconds.is_multiple_of(*unit)) {
+        Some(unit) => format!("{}{}", seconds / unit, unit_name(unit, seconds / unit)),
+        None => format_duration(Duration::from_secs(seconds)).to_string(),
+    }
+}
+
+fn unit_name(unit: u64, count: u64) -> &'static str {
+    match (unit, count) {
+        (YEAR, 1) => "year",
+        (YEAR, _) => "years",
+        (MONTH, 1) => "month",
+        (MONTH, _) => "months",
+        (DAY, 1) => "day",
+        (DAY, _) => "days",
+        (HOUR, _) => "h",
+        _ => "m",
+    }
+}
@@ -0,0 +1,189 @@
+use std::{collections::{HashMap, HashSet}, fs::Metadata, path::{Path, PathBuf}, sync::{Arc, Mutex}};
+
+#[cfg(unix)]
+pub type FileId = (u64, u64);
+
+#[cfg(unix)]
+pub fn file_id(_path: &Path, metadata: &Metadata) -> Option<FileId> {
+    use std::os::unix::fs::MetadataExt;
+
+    Some((metadata.dev(), metadata.ino()))
+}
+
+#[cfg(unix)]
+pub fn device_id(metadata: &Metadata) -> Option<u64> {
+    use std::os::unix::fs::MetadataExt;
+
+    Some(metadata.dev())
+}
+
+#[cfg(not(unix))]
+pub type FileId = std::path::PathBuf;
+
+#[cfg(not(unix))]
+pub fn file_id(path: &Path, _metadata: &Metadata) -> Option<FileId> {
+    std::fs::canonicalize(path).ok()
+}
+
+#[cfg(not(unix))]
+pub fn device_id(_metadata: &Metadata) -> Option<u64> {
+    None
+}
+
+/// Returns `false` when the file was inserted before.
+fn insert_id(ids: &Mutex<HashSet<FileId>>, path: &Path, metadata: &Metadata) -> bool {
+    let id = match file_id(path, metadata) {
+        Some(id) => id,
+        None => return true,
+    };
+
+    match ids.lock() {
+        Ok(mut ids) => ids.insert(id),
+        Err(_) => true,
+    }
+}
+
+/// Directories already entered by a walk, shared between its tasks to break cycles.
+#[derive(Clone, Default)]
+pub struct VisitedDirs {
+    ids: Arc<Mutex<HashSet<FileId>>>,
+}
+
+impl VisitedDirs {
+    /// Returns `false` when the directory was visited before.
+    pub fn insert(&self, path: &Path, metadata: &Metadata) -> bool {
+        insert_id(&self.ids, path, metadata)
+    }
+}
+
+/// Links of hard-linked files seen so far, shared between the size calculations of a scan.
+/// A file is only counted once every one of its links was seen: deleting the directories
+/// holding some of them frees nothing while another link (e.g. in the pnpm store) remains.
+#[derive(Clone, Default)]
+pub struct CountedFiles {
+    counts: Arc<Mutex<LinkCounts>>,
+}
+
+#[derive(Default)]
+struct LinkCounts {
+    /// Links of each file seen by the measured directories.
+    links: HashMap<FileId, u64>,
+    /// Directory whose size includes the file, the one that saw its last link.
+    owners: HashMap<FileId, PathBuf>,
+    /// Files each measured directory saw a link of, to take back before measuring it again.
+    seen_by: HashMap<PathBuf, Vec<FileId>>,
+}
+
+impl CountedFiles {
+    /// Returns `true` when this is the last of the `links` links of the file to be seen, so
+    /// the size of `dir`, the directory being measured, includes it.
+    // `FileId` is only `Copy` on unix.
+    #[allow(clippy::clone_on_copy)]
+    pub fn insert(&self, dir: &Path, path: &Path, metadata: &Metadata, links: u64) -> bool {
+        let id = match file_id(path, metadata) {
+            Some(id) => id,
+            None => return true,
+        };
+
+        let mut counts = match self.counts.lock() {
+            Ok(counts) => counts,
+            Err(_) => return true,
+        };
+
+        counts.seen_by.entry(dir.to_path_buf()).or_default().push(id.clone());
+        let count = counts.links.entry(id.clone()).or_insert(0);
+        *count += 1;
+        if *count < links || counts.owners.contains_key(&id) {
+            return false;
+        }
+
+        counts.owners.insert(id, dir.to_path_buf());
+        true
+    }
+
+    /// Take back the links seen while measuring `dir`, before it is measured again.
+    pub fn forget(&self, dir: &Path) {
+        let mut counts = match self.counts.lock() {
+            Ok(counts) => counts,
+            Err(_) => return,
+        };
+
+        for id in counts.seen_by.remove(dir).unwrap_or_default() {
+            if let Some(count) = counts.links.get_mut(&id) {
+                *count -= 1;
+                if *count == 0 {
+                    counts.links.remove(&id);
+                }
+            }
+            if counts.owners.get(&id).map(|owner| owner == dir).unwrap_or(false) {
+                counts.owners.remove(&id);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    /// A file with links in `a`, `a` again and `b`, below a fresh temporary directory.
+    fn linked_file(name: &str) -> (PathBuf, [PathBuf; 3]) {
+        let dir = std::env::temp_dir().join(format!("rkm-links-test-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("a")).unwrap();
+        fs::create_dir_all(dir.join("b")).unwrap();
+
+        let links = [dir.join("a").join("1"), dir.join("a").join("2"), dir.join("b").join("1")];
+        fs::write(&links[0], "content").unwrap();
+        fs::hard_link(&links[0], &links[1]).unwrap();
+        fs::hard_link(&links[0], &links[2]).unwrap();
+        (dir, links)
+    }
+
+    fn insert(counted: &CountedFiles, link: &Path) -> bool {
+        let metadata = fs::symlink_metadata(link).unwrap();
+        counted.insert(link.parent().unwrap(), link, &metadata, 3)
+    }
+
+    #[test]
+    fn counted_by_the_directory_seeing_the_last_link() {
+        let (dir, links) = linked_file("last");
+        let counted = CountedFiles::default();
+
+        assert!(!insert(&counted, &links[0]));
+        assert!(!insert(&counted, &links[1]));
+        assert!(insert(&counted, &links[2]));
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn measuring_again_gives_the_same_result() {
+        let (dir, links) = linked_file("again");
+        let counted = CountedFiles::default();
+        let (a, b) = (links[0].parent().unwrap(), links[2].parent().unwrap());
+
+        assert!(!insert(&counted, &links[0]));
+        assert!(!insert(&counted, &links[1]));
+        assert!(insert(&counted, &links[2]));
+
+        // `a` did not include the file and still does not: `b` does.
+        counted.forget(a);
+        assert!(!insert(&counted, &links[0]));
+        assert!(!insert(&counted, &links[1]));
+
+        // `b` included it and includes it again.
+        counted.forget(b);
+        assert!(insert(&counted, &links[2]));
+
+        // Once both are taken back, the one seeing the last link includes it.
+        counted.forget(a);
+        counted.forget(b);
+        assert!(!insert(&counted, &links[2]));
+        assert!(!insert(&counted, &links[0]));
+        assert!(insert(&counted, &links[1]));
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,273 @@
+use std::{path::{Path, PathBuf}, sync::Arc, fs};
+
+pub const IGNORE_FILE: &str = ".rkmignore";
+
+fn match_class(class: &[char], c: char) -> bool {
+    let (negated, class) = match class.first() {
+        Some('!') | Some('^') => (true, &class[1..]),
+        _ => (false, class),
+    };
+
+    let mut matched = false;
+    let mut index = 0;
+    while index < class.len() {
+        if index + 2 < class.len() && class[index + 1] == '-' {
+            matched |= class[index] <= c && c <= class[index + 2];
+            index += 3;
+        } else {
+            matched |= class[index] == c;
+            index += 1;
+        }
+    }
+
+    matched != negated
+}
+
+/// Match `text` against a gitignore-style glob: `*` and `?` stay within one path segment,
+/// `**/` spans any number of directories and `[...]` is a character class.
+fn glob_match(pattern: &[char], text: &[char]) -> bool {
+    let (first, rest) = match pattern.split_first() {
+        Some(split) => split,
+        None => return text.is_empty(),
+    };
+
+    match first {
+        '*' if rest.first() == Some(&'*') => {
+            let rest = &rest[1..];
+            match rest.split_first() {
+                None => true,
+                Some(('/', rest)) => (0..=text.len())
+                    .filter(|&index| index == 0 || text[index - 1] == '/')
+                    .any(|index| glob_match(rest, &text[index..])),
+                Some(_) => glob_match(&pattern[1..], text),
+            }
+        },
+        '*' => {
+            for index in 0..=text.len() {
+                if glob_match(rest, &text[index..]) {
+                    return true;
+                }
+                if index < text.len() && text[index] == '/' {
+                    return false;
+                }
+            }
+            false
+        },
+        '?' => match text.split_first() {
+            Some((c, text)) if *c != '/' => glob_match(rest, text),
+            _ => false,
+        },
+        '[' => {
+            let end = match rest.iter().skip(1).position(|c| *c == ']') {
+                Some(end) => end + 1,
+                None => return text.first() == Some(&'[') && glob_match(rest, &text[1..]),
+            };
+
+            match text.split_first() {
+                Some((c, text)) if *c != '/' && match_class(&rest[..end], *c) => glob_match(&rest[end + 1..], text),
+                _ => false,
+            }
+        },
+        '\\' if !rest.is_empty() => {
+            text.first() == Some(&rest[0]) && glob_match(&rest[1..], &text[1..])
+        },
+        c => text.first() == Some(c) && glob_match(rest, &text[1..]),
+    }
+}
+
+struct IgnorePattern {
+    glob: Vec<char>,
+    negated: bool,
+    anchored: bool,
+}
+
+impl IgnorePattern {
+    fn parse(line: &str) -> Option<IgnorePattern> {
+        let line = line.trim_end();
+        if line.is_empty() || line.starts_with('#') {
+            return None;
+        }
+
+        let (negated, line) = match line.strip_prefix('!') {
+            Some(line) => (true, line),
+            None => (false, line.strip_prefix('\\').unwrap_or(line)),
+        };
+
+        // Only directories are walked, so a trailing `/` carries no extra meaning.
+        let line = line.trim_end_matches('/');
+        let anchored = line.contains('/');
+        let line = line.trim_start_matches('/');
+        if line.is_empty() {
+            return None;
+        }
+
+        Some(IgnorePattern { glob: line.chars().collect(), negated, anchored })
+    }
+
+    fn is_match(&self, relative: &[char], name: &[char]) -> bool {
+        if self.anchored {
+            glob_match(&self.glob, relative)
+        } else {
+            glob_match(&self.glob, name)
+        }
+    }
+}
+
+/// Ignore patterns of one directory, chained to the patterns inherited from its parents.
+pub struct IgnoreRules {
+    base: PathBuf,
+    patterns: Vec<IgnorePattern>,
+    parent: Option<Arc<IgnoreRules>>,
+}
+
+impl IgnoreRules {
+    pub fn new(base: &Path, lines: &[String]) -> Arc<IgnoreRules> {
+        Arc::new(IgnoreRules {
+            base: base.to_path_buf(),
+            patterns: lines.iter().filter_map(|line| IgnorePattern::parse(line)).collect(),
+            parent: None,
+        })
+    }
+
+    /// Rules for `dir`, extended with its ignore file when it has one.
+    pub fn child(self: &Arc<Self>, dir: &Path) -> Arc<IgnoreRules> {
+        let content = match fs::read_to_string(dir.join(IGNORE_FILE)) {
+            Ok(content) => content,
+            Err(_) => return self.clone(),
+        };
+
+        Arc::new(IgnoreRules {
+            base: dir.to_path_buf(),
+            patterns: content.lines().filter_map(IgnorePattern::parse).collect(),
+            parent: Some(self.clone()),
+        })
+    }
+
+    /// The deepest rules decide first, and within them the last matching pattern wins.
+    pub fn is_ignored(&self, path: &Path) -> bool {
+        let mut rules = Some(self);
+
+        while let Some(current) = rules {
+            if let Ok(relative) = path.strip_prefix(&current.base) {
+                let relative: Vec<char> = relative
+                    .components()
+                    .map(|component| component.as_os_str().to_string_lossy())
+                    .collect::<Vec<_>>()
+                    .join("/")
+                    .chars()
+                    .collect();
+                let name_start = relative.iter().rposition(|c| *c == '/').map(|index| index + 1).unwrap_or(0);
+
+                let found = current.patterns.iter().rev().find(|pattern| pattern.is_match(&relative, &relative[name_start..]));
+                if let Some(pattern) = found {
+                    return !pattern.negated;
+                }
+            }
+
+            rules = current.parent.as_deref();
+        }
+
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn glob(pattern: &str, text: &str) -> bool {
+        glob_match(&pattern.chars().collect::<Vec<_>>(), &text.chars().collect::<Vec<_>>())
+    }
+
+    fn ignored(lines: &[&str], path: &str) -> bool {
+        let lines: Vec<String> = lines.iter().map(|line| line.to_string()).collect();
+        IgnoreRules::new(Path::new("/root"), &lines).is_ignored(&Path::new("/root").join(path))
+    }
+
+    #[test]
+    fn star_stays_within_a_segment() {
+        assert!(glob("*.cache", "build.cache"));
+        assert!(glob("a*", "a"));
+        assert!(!glob("a*", "a/b"));
+        assert!(!glob("*.cache", "dir/build.cache"));
+    }
+
+    #[test]
+    fn question_mark_matches_one_character_but_not_a_separator() {
+        assert!(glob("dist?", "dist2"));
+        assert!(!glob("dist?", "dist"));
+        assert!(!glob("a?b", "a/b"));
+    }
+
+    #[test]
+    fn double_star_spans_directories() {
+        assert!(glob("**/target", "target"));
+        assert!(glob("**/target", "a/b/target"));
+        assert!(!glob("**/target", "a/xtarget"));
+        assert!(glob("a/**/b", "a/b"));
+        assert!(glob("a/**/b", "a/x/y/b"));
+        assert!(glob("a/**", "a/x/y"));
+    }
+
+    #[test]
+    fn character_classes() {
+        assert!(glob("v[0-9]", "v7"));
+        assert!(!glob("v[0-9]", "vx"));
+        assert!(glob("v[!0-9]", "vx"));
+        assert!(glob("v[^0-9]", "vx"));
+        assert!(glob("[ab]c", "bc"));
+        assert!(!glob("[ab]c", "cc"));
+        assert!(glob("a[b", "a[b"));
+    }
+
+    #[test]
+    fn escaped_characters_match_literally() {
+        assert!(glob("\\*", "*"));
+        assert!(!glob("\\*", "a"));
+    }
+
+    #[test]
+    fn unanchored_patterns_match_the_name_at_any_depth() {
+        assert!(ignored(&["vendor"], "vendor"));
+        assert!(ignored(&["vendor"], "a/b/vendor"));
+        assert!(!ignored(&["vendor"], "a/vendors"));
+    }
+
+    #[test]
+    fn patterns_with_a_slash_are_anchored_to_the_base() {
+        assert!(ignored(&["/vendor"], "vendor"));
+        assert!(!ignored(&["/vendor"], "a/vendor"));
+        assert!(ignored(&["a/vendor"], "a/vendor"));
+        assert!(!ignored(&["a/vendor"], "b/a/vendor"));
+        assert!(ignored(&["vendor/"], "a/vendor"));
+    }
+
+    #[test]
+    fn the_last_matching_pattern_wins() {
+        assert!(!ignored(&["pkg-*", "!pkg-keep"], "pkg-keep"));
+        assert!(ignored(&["pkg-*", "!pkg-keep"], "pkg-drop"));
+        assert!(ignored(&["!pkg-keep", "pkg-*"], "pkg-keep"));
+    }
+
+    #[test]
+    fn comments_and_blank_lines_are_skipped() {
+        let lines = ["# vendor", "", "\\#hash"];
+        assert!(!ignored(&lines, "# vendor"));
+        assert!(!ignored(&lines, ""));
+        assert!(ignored(&lines, "#hash"));
+    }
+
+    #[test]
+    fn deeper_rules_override_their_parents() {
+        let dir = std::env::temp_dir().join(format!("rkm-ignore-test-{}", std::process::id()));
+        fs::create_dir_all(dir.join("sub")).unwrap();
+        fs::write(dir.join("sub").join(IGNORE_FILE), "!keep\n").unwrap();
+
+        let root = IgnoreRules::new(&dir, &["keep".to_owned()]);
+        let sub = root.child(&dir.join("sub"));
+        assert!(root.is_ignored(&dir.join("keep")));
+        assert!(!sub.is_ignored(&dir.join("sub").join("keep")));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
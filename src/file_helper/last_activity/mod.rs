@@ -0,0 +1,89 @@
+use std::{fs::{self, DirEntry}, path::{Path, PathBuf}, sync::{Arc, atomic::{AtomicU64, Ordering}}, time::{Duration, SystemTime, UNIX_EPOCH}};
+
+use super::{IgnoreRules, SearchOption, WorkerPool, TaskGroup, CancelToken, device_id, ignore_rules_for, is_quarantined};
+
+/// Levels of project directories looked into below the one holding the manifest and lockfile.
+/// Recent edits are nearly always that shallow, and a project that is in fact a home directory
+/// is not walked whole.
+const SOURCE_DEPTH: usize = 3;
+
+/// Settings shared by the tasks of one walk.
+struct ActivityWalk {
+    target: PathBuf,
+    option: Arc<SearchOption>,
+    /// Device of the searched root, for `-x`.
+    root_device: Option<u64>,
+    newest: AtomicU64,
+}
+
+impl ActivityWalk {
+    /// Whether a directory of the project holds generated or tool data rather than project
+    /// files, or is kept out of the search.
+    fn is_skipped_dir(&self, entry: &DirEntry, metadata: &fs::Metadata, rules: &IgnoreRules) -> bool {
+        let file_name = entry.file_name();
+        let name = file_name.to_string_lossy();
+        let path = entry.path();
+
+        name.starts_with('.')
+            || is_quarantined(&file_name)
+            || self.option.profiles.is_target_name(&name)
+            || path == self.target
+            || rules.is_ignored(&path)
+            || self.option.skipped_mounts.contains(&path)
+            || (self.option.one_file_system && self.root_device.is_some() && device_id(metadata) != self.root_device)
+    }
+}
+
+fn newest_spawn(path: PathBuf, depth: usize, rules: Arc<IgnoreRules>, walk: Arc<ActivityWalk>, group: &TaskGroup) {
+    group.spawn(move |group| newest_in_dir(&path, depth, rules, walk, group));
+}
+
+/// `rules` are those that apply to the entries of `path`.
+fn newest_in_dir(path: &Path, depth: usize, rules: Arc<IgnoreRules>, walk: Arc<ActivityWalk>, group: &TaskGroup) {
+    let children: Vec<DirEntry> = {
+        let _permit = group.pool().open_dir();
+        match path.read_dir() {
+            Ok(children) => children.filter_map(|entry| entry.ok()).collect(),
+            Err(_) => return,
+        }
+    };
+
+    for entry in children {
+        let metadata = match entry.metadata() {
+            Ok(metadata) => metadata,
+            Err(_) => continue,
+        };
+
+        if metadata.is_dir() {
+            if depth < SOURCE_DEPTH && !walk.is_skipped_dir(&entry, &metadata, &rules) {
+                let path = entry.path();
+                newest_spawn(path.clone(), depth + 1, rules.child(&path), walk.clone(), group);
+            }
+            continue;
+        }
+
+        let modified = metadata.modified().ok().and_then(|time| time.duration_since(UNIX_EPOCH).ok());
+        if let Some(modified) = modified {
+            walk.newest.fetch_max(modified.as_secs(), Ordering::Relaxed);
+        }
+    }
+}
+
+/// Newest modification time among the files of the project owning `target` (its parent
+/// directory): its manifest and lockfile, and the files up to `SOURCE_DEPTH` levels below.
+/// `target`, hidden and other target directories are left out, and so is whatever the search
+/// of `root` skips.
+pub async fn get_last_activity(pool: WorkerPool, root: Arc<Path>, target: PathBuf, option: Arc<SearchOption>, cancel: CancelToken) -> Option<SystemTime> {
+    let project = target.parent()?.to_path_buf();
+    let rules = ignore_rules_for(&root, &project, &option.excludes);
+    let root_device = fs::metadata(&root).ok().and_then(|metadata| device_id(&metadata));
+    let walk = Arc::new(ActivityWalk { target, option, root_device, newest: AtomicU64::new(0) });
+    let result = walk.clone();
+
+    pool.run(cancel, move |group| newest_in_dir(&project, 0, rules, walk, group)).await;
+
+    match result.newest.load(Ordering::Relaxed) {
+        0 => None,
+        secs => Some(UNIX_EPOCH + Duration::from_secs(secs)),
+    }
+}
@@ -1,7 +1,35 @@
 mod cal_file_size;
 mod search_file;
 mod convert_file_size;
+mod target_profile;
+mod ignore_rule;
+mod file_id;
+mod mount_info;
+mod worker_pool;
+mod cancel_token;
+mod watcher;
+mod last_activity;
+mod convert_age;
+mod size_cache;
+mod scan_error;
+mod package_manifest;
+mod trash;
+mod quarantine;
 
 pub use cal_file_size::*;
 pub use search_file::*;
-pub use convert_file_size::*;
\ No newline at end of file
+pub use convert_file_size::*;
+pub use target_profile::*;
+pub use ignore_rule::*;
+pub use file_id::*;
+pub use mount_info::*;
+pub use worker_pool::*;
+pub use cancel_token::*;
+pub use watcher::*;
+pub use last_activity::*;
+pub use convert_age::*;
+pub use size_cache::*;
+pub use scan_error::*;
+pub use package_manifest::*;
+pub use trash::*;
+pub use quarantine::*;
\ No newline at end of file
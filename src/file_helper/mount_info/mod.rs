@@ -0,0 +1,153 @@
+use std::{collections::HashSet, fs, path::{Path, PathBuf}};
+
+const MOUNT_INFO: &str = "/proc/self/mountinfo";
+
+const PSEUDO_FS: [&str; 20] = [
+    "proc", "sysfs", "devtmpfs", "devpts", "cgroup", "cgroup2", "securityfs", "debugfs",
+    "tracefs", "configfs", "fusectl", "mqueue", "hugetlbfs", "pstore", "bpf", "binfmt_misc",
+    "autofs", "efivarfs", "nsfs", "selinuxfs",
+];
+
+const NETWORK_FS: [&str; 12] = [
+    "nfs", "nfs4", "cifs", "smb3", "smbfs", "sshfs", "9p", "afs", "ceph", "glusterfs",
+    "rpc_pipefs", "davfs",
+];
+
+pub struct Mount {
+    pub point: PathBuf,
+    pub fs_type: String,
+}
+
+impl Mount {
+    /// Pseudo and network filesystems, plus FUSE mounts other than local block devices.
+    pub fn is_virtual(&self) -> bool {
+        let fs_type = self.fs_type.as_str();
+        PSEUDO_FS.contains(&fs_type)
+            || NETWORK_FS.contains(&fs_type)
+            || (fs_type.starts_with("fuse") && fs_type != "fuseblk")
+    }
+}
+
+/// Undo the octal escaping (`\040` for a space) used by the kernel in mountinfo.
+fn unescape(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut result = vec![];
+    let mut index = 0;
+
+    while index < bytes.len() {
+        let digits = bytes.get(index + 1..index + 4).unwrap_or_default();
+        if bytes[index] == b'\\' && digits.len() == 3 && digits.iter().all(|b| (b'0'..=b'7').contains(b)) {
+            let code = digits.iter().fold(0u32, |code, digit| code * 8 + u32::from(digit - b'0'));
+            result.push(code as u8);
+            index += 4;
+            continue;
+        }
+        result.push(bytes[index]);
+        index += 1;
+    }
+
+    String::from_utf8_lossy(&result).into_owned()
+}
+
+/// Parse one line of `/proc/self/mountinfo`:
+/// `id parent major:minor root mount-point options [optional...] - fs-type source super-options`.
+fn parse_line(line: &str) -> Option<Mount> {
+    let mut fields = line.split(' ');
+    let point = fields.nth(4)?;
+    let fs_type = fields.skip_while(|field| *field != "-").nth(1)?;
+
+    Some(Mount { point: PathBuf::from(unescape(point)), fs_type: fs_type.to_owned() })
+}
+
+pub fn read_mounts() -> Vec<Mount> {
+    match fs::read_to_string(MOUNT_INFO) {
+        Ok(content) => content.lines().filter_map(parse_line).collect(),
+        Err(_) => vec![],
+    }
+}
+
+/// Mount points the search must not enter.
+#[derive(Default)]
+pub struct SkippedMounts {
+    points: HashSet<PathBuf>,
+}
+
+impl SkippedMounts {
+    /// Skip every virtual mount whose filesystem type is not in `allowed`.
+    pub fn new(mounts: Vec<Mount>, allowed: &[String]) -> SkippedMounts {
+        let points = mounts
+            .into_iter()
+            .filter(|mount| mount.is_virtual() && !allowed.contains(&mount.fs_type))
+            .map(|mount| mount.point)
+            .collect();
+
+        SkippedMounts { points }
+    }
+
+    pub fn contains(&self, path: &Path) -> bool {
+        self.points.contains(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_a_line_with_optional_fields() {
+        let mount = parse_line("36 35 98:0 /mnt1 /mnt/parent rw,noatime master:1 shared:2 - ext3 /dev/root rw,errors=continue").unwrap();
+        assert_eq!(mount.point, PathBuf::from("/mnt/parent"));
+        assert_eq!(mount.fs_type, "ext3");
+        assert!(!mount.is_virtual());
+    }
+
+    #[test]
+    fn parses_a_line_without_optional_fields() {
+        let mount = parse_line("22 1 0:21 / /proc rw,nosuid - proc proc rw").unwrap();
+        assert_eq!(mount.point, PathBuf::from("/proc"));
+        assert!(mount.is_virtual());
+    }
+
+    #[test]
+    fn unescapes_the_mount_point() {
+        let mount = parse_line(r"40 25 0:35 / /media/my\040disk\011tab rw - vfat /dev/sdb1 rw").unwrap();
+        assert_eq!(mount.point, PathBuf::from("/media/my disk\ttab"));
+        assert_eq!(unescape(r"back\134slash"), r"back\slash");
+    }
+
+    #[test]
+    fn incomplete_escapes_are_kept() {
+        assert_eq!(unescape(r"a\04"), r"a\04");
+        assert_eq!(unescape(r"a\089"), r"a\089");
+        assert_eq!(unescape("trailing\\"), "trailing\\");
+    }
+
+    #[test]
+    fn fuse_mounts_are_virtual_except_block_devices() {
+        let sshfs = parse_line("58 25 0:50 / /home/me/remote rw,nosuid,nodev shared:30 - fuse.sshfs me@host:/ rw,user_id=1000").unwrap();
+        assert_eq!(sshfs.fs_type, "fuse.sshfs");
+        assert!(sshfs.is_virtual());
+
+        let ntfs = parse_line("59 25 8:17 / /mnt/windows rw - fuseblk /dev/sdb1 rw").unwrap();
+        assert!(!ntfs.is_virtual());
+    }
+
+    #[test]
+    fn truncated_lines_are_skipped() {
+        assert!(parse_line("36 35 98:0 /mnt1").is_none());
+        assert!(parse_line("36 35 98:0 /mnt1 /mnt/parent rw master:1").is_none());
+    }
+
+    #[test]
+    fn allowed_types_are_not_skipped() {
+        let mounts = vec![
+            Mount { point: PathBuf::from("/remote"), fs_type: "fuse.sshfs".to_owned() },
+            Mount { point: PathBuf::from("/nfs"), fs_type: "nfs4".to_owned() },
+            Mount { point: PathBuf::from("/home"), fs_type: "ext4".to_owned() },
+        ];
+        let skipped = SkippedMounts::new(mounts, &["fuse.sshfs".to_owned()]);
+        assert!(!skipped.contains(Path::new("/remote")));
+        assert!(skipped.contains(Path::new("/nfs")));
+        assert!(!skipped.contains(Path::new("/home")));
+    }
+}
@@ -0,0 +1,186 @@
+use std::{fs, path::{Path, PathBuf}};
+
+const PACKAGE_MANIFEST: &str = "package.json";
+
+/// A package installed directly in a `node_modules` directory.
+pub struct InstalledPackage {
+    pub name: String,
+    pub version: String,
+    pub path: PathBuf,
+}
+
+/// Read the four hex digits of a `\uXXXX` escape.
+fn read_code_unit(chars: &mut impl Iterator<Item = (usize, char)>) -> Option<u32> {
+    let mut unit = 0;
+    for _ in 0..4 {
+        unit = unit * 16 + chars.next()?.1.to_digit(16)?;
+    }
+
+    Some(unit)
+}
+
+/// Decode a `\uXXXX` escape whose `u` was just read, joining a surrogate pair with the
+/// `\uXXXX` escape that must follow it. Lone surrogates become U+FFFD.
+fn read_unicode_escape(chars: &mut (impl Iterator<Item = (usize, char)> + Clone)) -> Option<char> {
+    let unit = read_code_unit(chars)?;
+    if !(0xD800..0xDC00).contains(&unit) {
+        return Some(char::from_u32(unit).unwrap_or(char::REPLACEMENT_CHARACTER));
+    }
+
+    let mut low_chars = chars.clone();
+    let low = match (low_chars.next(), low_chars.next()) {
+        (Some((_, '\\')), Some((_, 'u'))) => read_code_unit(&mut low_chars)?,
+        _ => return Some(char::REPLACEMENT_CHARACTER),
+    };
+    if !(0xDC00..0xE000).contains(&low) {
+        return Some(char::REPLACEMENT_CHARACTER);
+    }
+
+    *chars = low_chars;
+    char::from_u32(0x10000 + ((unit - 0xD800) << 10) + (low - 0xDC00))
+}
+
+/// Read a string token starting right after its opening quote, returning it and the rest.
+fn read_string(text: &str) -> Option<(String, &str)> {
+    let mut value = String::new();
+    let mut chars = text.char_indices();
+
+    while let Some((index, c)) = chars.next() {
+        match c {
+            '"' => return Some((value, &text[index + 1..])),
+            '\\' => match chars.next()?.1 {
+                'n' => value.push('\n'),
+                't' => value.push('\t'),
+                'r' => value.push('\r'),
+                'b' => value.push('\u{8}'),
+                'f' => value.push('\u{c}'),
+                'u' => value.push(read_unicode_escape(&mut chars)?),
+                escaped => value.push(escaped),
+            },
+            _ => value.push(c),
+        }
+    }
+
+    None
+}
+
+/// Value of the string field `key` of the top-level object of a JSON document, without
+/// looking into nested objects such as `"author": { "name": .. }`.
+fn top_level_string(json: &str, key: &str) -> Option<String> {
+    let mut depth = 0;
+    let mut rest = json;
+
+    while let Some(c) = rest.chars().next() {
+        rest = &rest[c.len_utf8()..];
+        match c {
+            '{' | '[' => depth += 1,
+            '}' | ']' => depth -= 1,
+            '"' => {
+                let (token, after) = read_string(rest)?;
+                rest = after;
+
+                let after_colon = match rest.trim_start().strip_prefix(':') {
+                    Some(after_colon) if depth == 1 => after_colon.trim_start(),
+                    _ => continue,
+                };
+                if token != key {
+                    continue;
+                }
+
+                return match after_colon.strip_prefix('"') {
+                    Some(value) => read_string(value).map(|(value, _)| value),
+                    None => None,
+                };
+            },
+            _ => {},
+        }
+    }
+
+    None
+}
+
+fn read_package(dir: PathBuf) -> Option<InstalledPackage> {
+    let json = fs::read_to_string(dir.join(PACKAGE_MANIFEST)).ok()?;
+    let name = top_level_string(&json, "name")?;
+    let version = top_level_string(&json, "version")?;
+
+    Some(InstalledPackage { name, version, path: dir })
+}
+
+/// Directories of `dir`, symlinks to directories included: pnpm links every package of
+/// `node_modules` to its copy in `node_modules/.pnpm`.
+fn child_dirs(dir: &Path) -> Vec<PathBuf> {
+    match dir.read_dir() {
+        Ok(entries) => entries
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| fs::metadata(entry.path()).map(|metadata| metadata.is_dir()).unwrap_or(false))
+            .map(|entry| entry.path())
+            .collect(),
+        Err(_) => vec![],
+    }
+}
+
+/// Packages installed in `node_modules/<package>` and `node_modules/@scope/<package>`.
+pub fn installed_packages(node_modules: &Path) -> Vec<InstalledPackage> {
+    let mut packages = vec![];
+
+    for dir in child_dirs(node_modules) {
+        let is_scope = dir.file_name().and_then(|name| name.to_str()).map(|name| name.starts_with('@')).unwrap_or(false);
+        match is_scope {
+            true => packages.extend(child_dirs(&dir).into_iter().filter_map(read_package)),
+            false => packages.extend(read_package(dir)),
+        }
+    }
+
+    packages
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_top_level_fields() {
+        let json = r#"{ "name": "lodash", "version": "4.17.21" }"#;
+        assert_eq!(top_level_string(json, "name").as_deref(), Some("lodash"));
+        assert_eq!(top_level_string(json, "version").as_deref(), Some("4.17.21"));
+    }
+
+    #[test]
+    fn ignores_nested_objects() {
+        let json = r#"{ "author": { "name": "someone" }, "deps": [{ "name": "x" }], "name": "pkg" }"#;
+        assert_eq!(top_level_string(json, "name").as_deref(), Some("pkg"));
+    }
+
+    #[test]
+    fn ignores_string_values_named_like_the_key() {
+        let json = r#"{ "description": "name", "name": "pkg" }"#;
+        assert_eq!(top_level_string(json, "name").as_deref(), Some("pkg"));
+    }
+
+    #[test]
+    fn missing_or_non_string_field_is_none() {
+        assert_eq!(top_level_string(r#"{ "name": "pkg" }"#, "version"), None);
+        assert_eq!(top_level_string(r#"{ "version": 1 }"#, "version"), None);
+        assert_eq!(top_level_string(r#"{ "version": "1.0""#, "name"), None);
+    }
+
+    #[test]
+    fn decodes_escapes() {
+        let json = r#"{ "name": "a\"b\\c\/d\te" }"#;
+        assert_eq!(top_level_string(json, "name").as_deref(), Some("a\"b\\c/d\te"));
+    }
+
+    #[test]
+    fn decodes_unicode_escapes() {
+        assert_eq!(top_level_string(r#"{ "name": "caf\u00e9" }"#, "name").as_deref(), Some("café"));
+        assert_eq!(top_level_string(r#"{ "name": "\ud83d\ude00!" }"#, "name").as_deref(), Some("😀!"));
+        assert_eq!(top_level_string(r#"{ "name": "\ud83dx" }"#, "name").as_deref(), Some("\u{fffd}x"));
+        assert_eq!(top_level_string(r#"{ "name": "\u00zz" }"#, "name"), None);
+    }
+
+    #[test]
+    fn matches_escaped_keys() {
+        assert_eq!(top_level_string(r#"{ "n\u0061me": "pkg" }"#, "name").as_deref(), Some("pkg"));
+    }
+}
@@ -0,0 +1,149 @@
+use std::{ffi::OsStr, fs, io, path::{Path, PathBuf}, process};
+
+const MANIFEST_PREFIX: &str = "quarantine-";
+const QUARANTINE_MARKER: &str = ".quarantine-";
+
+/// Hidden sibling of `path` it is renamed to while in quarantine. Staying in the same directory
+/// keeps it on the same filesystem, so moving it there and back is a single rename.
+fn quarantine_path(path: &Path, attempt: usize) -> io::Result<PathBuf> {
+    let (parent, name) = match (path.parent(), path.file_name()) {
+        (Some(parent), Some(name)) => (parent, name.to_string_lossy()),
+        _ => return Err(io::Error::new(io::ErrorKind::InvalidInput, "cannot quarantine a root directory")),
+    };
+
+    let suffix = match attempt {
+        0 => String::new(),
+        _ => format!(".{}", attempt + 1),
+    };
+    Ok(parent.join(format!(".{}{}{}{}", name, QUARANTINE_MARKER, process::id(), suffix)))
+}
+
+/// Whether a file name is that of a quarantined directory. Searches skip those, even with
+/// `--hidden`, as they are on their way out.
+pub fn is_quarantined(name: &OsStr) -> bool {
+    name.to_string_lossy().contains(QUARANTINE_MARKER)
+}
+
+/// Move `path` out of the way and return where it went, so it can be restored or purged later.
+pub fn quarantine(path: &Path) -> io::Result<PathBuf> {
+    let mut attempt = 0;
+    loop {
+        let moved = quarantine_path(path, attempt)?;
+        // `rename` replaces an empty directory silently, so an existing name is skipped first.
+        if fs::symlink_metadata(&moved).is_err() {
+            fs::rename(path, &moved)?;
+            return Ok(moved);
+        }
+        attempt += 1;
+    }
+}
+
+/// Move a quarantined directory back, unless something was created at its place meanwhile.
+pub fn restore(moved: &Path, path: &Path) -> io::Result<()> {
+    if fs::symlink_metadata(path).is_ok() {
+        return Err(io::Error::new(io::ErrorKind::AlreadyExists, "the original path exists again"));
+    }
+
+    fs::rename(moved, path)
+}
+
+/// Delete a quarantined directory for good; a symlinked target only loses the link.
+pub fn purge(moved: &Path) -> io::Result<()> {
+    match fs::symlink_metadata(moved)?.file_type().is_symlink() {
+        true => fs::remove_file(moved),
+        false => remove_dir_all::remove_dir_all(moved),
+    }
+}
+
+/// File in `dir` listing what this process has in quarantine.
+pub fn manifest_file(dir: &Path) -> PathBuf {
+    dir.join(format!("{}{}", MANIFEST_PREFIX, process::id()))
+}
+
+/// Write down the quarantined paths, so they are purged even if the process never gets to do
+/// it. The manifest is removed once nothing is left in quarantine.
+pub fn write_manifest<'a>(file: &Path, moved: impl Iterator<Item = &'a Path>) -> io::Result<()> {
+    let mut content = String::new();
+    for path in moved {
+        // Paths that cannot be written on a single line are left for the shutdown to purge.
+        match path.to_str() {
+            Some(text) if !text.contains(['\n', '\r']) => {
+                content.push_str(text);
+                content.push('\n');
+            },
+            _ => continue,
+        }
+    }
+
+    if content.is_empty() {
+        return match fs::remove_file(file) {
+            Err(error) if error.kind() != io::ErrorKind::NotFound => Err(error),
+            _ => Ok(()),
+        };
+    }
+
+    if let Some(dir) = file.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(file, content)
+}
+
+/// Purge everything listed in a manifest, then remove it unless some paths are still there.
+pub fn purge_manifest(file: &Path) {
+    let content = match fs::read_to_string(file) {
+        Ok(content) => content,
+        Err(_) => return,
+    };
+
+    let mut remaining = vec![];
+    for line in content.lines() {
+        let moved = Path::new(line);
+        // Only quarantine renames are purged, whatever else the file may list.
+        if !moved.file_name().map(is_quarantined).unwrap_or(false) {
+            continue;
+        }
+        match purge(moved) {
+            Err(error) if error.kind() != io::ErrorKind::NotFound => remaining.push(moved),
+            _ => {},
+        }
+    }
+
+    let _ = write_manifest(file, remaining.into_iter());
+}
+
+#[cfg(unix)]
+fn is_running(pid: u32) -> bool {
+    let pid = match libc::pid_t::try_from(pid) {
+        Ok(pid) => pid,
+        Err(_) => return false,
+    };
+
+    // Signal 0 only checks the process exists; `EPERM` means it belongs to someone else.
+    let found = unsafe { libc::kill(pid, 0) == 0 };
+    found || io::Error::last_os_error().raw_os_error() == Some(libc::EPERM)
+}
+
+#[cfg(not(unix))]
+fn is_running(_pid: u32) -> bool {
+    // Without a way to tell, the quarantine of another instance is never touched.
+    true
+}
+
+/// Purge what processes that ended without purging left in quarantine, found next to
+/// `manifest`.
+pub fn purge_leftovers(manifest: &Path) {
+    let entries = match manifest.parent().map(fs::read_dir) {
+        Some(Ok(entries)) => entries,
+        _ => return,
+    };
+
+    for entry in entries.filter_map(|entry| entry.ok()) {
+        let pid = entry.file_name().to_str()
+            .and_then(|name| name.strip_prefix(MANIFEST_PREFIX))
+            .and_then(|pid| pid.parse::<u32>().ok());
+        match pid {
+            Some(pid) if pid != process::id() && !is_running(pid) => purge_manifest(&entry.path()),
+            _ => {},
+        }
+    }
+}
@@ -0,0 +1,67 @@
+use std::{fs::DirEntry, io, path::{Path, PathBuf}, sync::{Arc, Mutex}};
+
+/// A directory or file that could not be read.
+#[derive(Clone)]
+pub struct ScanError {
+    pub path: PathBuf,
+    pub kind: io::ErrorKind,
+}
+
+/// Errors met by searches, size calculations and deletions, shared between their tasks.
+#[derive(Clone, Default)]
+pub struct ErrorLog {
+    errors: Arc<Mutex<Vec<ScanError>>>,
+}
+
+impl ErrorLog {
+    pub fn push(&self, path: &Path, error: &io::Error) {
+        if let Ok(mut errors) = self.errors.lock() {
+            errors.push(ScanError { path: path.to_path_buf(), kind: error.kind() });
+        }
+    }
+
+    /// Entries of the directory at `path` and the number of errors met reading it.
+    pub fn read_dir(&self, path: &Path) -> (Vec<DirEntry>, u64) {
+        let entries = match path.read_dir() {
+            Ok(entries) => entries,
+            Err(error) => {
+                self.push(path, &error);
+                return (vec![], 1);
+            },
+        };
+
+        let mut result = vec![];
+        let mut error_count = 0;
+        for entry in entries {
+            match entry {
+                Ok(entry) => result.push(entry),
+                Err(error) => {
+                    self.push(path, &error);
+                    error_count += 1;
+                },
+            }
+        }
+
+        (result, error_count)
+    }
+
+    pub fn len(&self) -> usize {
+        self.errors.lock().map(|errors| errors.len()).unwrap_or(0)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The last `count` errors, oldest first.
+    pub fn latest(&self, count: usize) -> Vec<ScanError> {
+        match self.errors.lock() {
+            Ok(errors) => errors[errors.len().saturating_sub(count)..].to_vec(),
+            Err(_) => vec![],
+        }
+    }
+
+    pub fn all(&self) -> Vec<ScanError> {
+        self.latest(usize::MAX)
+    }
+}
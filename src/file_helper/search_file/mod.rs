@@ -1,83 +1,306 @@
-use std::{path::Path, fs::{DirEntry, self}, env, os::windows::prelude::*};
+use std::{io, path::{Path, PathBuf}, fs::{self, DirEntry, Metadata}, sync::Arc};
 
-use tokio::task::JoinSet;
+use tokio::sync::mpsc::Sender;
 
-const NODE_MODULE: &'static str = "node_modules";
-const ATTR_HIDDEN: u32 = 0x2;
+use super::{TargetProfiles, IgnoreRules, VisitedDirs, SkippedMounts, WorkerPool, TaskGroup, CancelToken, Watcher, ErrorLog, device_id, is_quarantined};
 
-fn is_match_name_dir(dir: &DirEntry) -> bool {
-    let path = dir.path();
-    let file_name = match path.to_str() {
-        Some(name) => name,
-        None => return false,
-    };
+pub struct SearchOption {
+    pub roots: Vec<PathBuf>,
+    pub include_hidden: bool,
+    pub follow_links: bool,
+    pub one_file_system: bool,
+    pub skipped_mounts: SkippedMounts,
+    pub max_depth: Option<usize>,
+    pub excludes: Vec<String>,
+    pub profiles: TargetProfiles,
+}
 
-    if !(file_name.ends_with(NODE_MODULE)) {
-        return false;
-    }
+/// A directory waiting to be searched.
+struct SearchDir {
+    root: Arc<Path>,
+    root_device: Option<u64>,
+    path: PathBuf,
+    depth: usize,
+    rules: Arc<IgnoreRules>,
+    visited: VisitedDirs,
+    watcher: Option<Watcher>,
+    errors: ErrorLog,
+}
 
-    true
+pub struct TargetDir {
+    pub root: Arc<Path>,
+    pub entry: DirEntry,
+    pub profile: String,
+    pub is_link: bool,
 }
 
+#[cfg(windows)]
 fn is_hidden(entry: &DirEntry) -> bool {
-    if cfg!(target_os = "windows") {
+    use std::os::windows::fs::MetadataExt;
+
+    const ATTR_HIDDEN: u32 = 0x2;
+
+    let metadata = match entry.metadata() {
+        Ok(metadata) => metadata,
+        Err(_) => return false,
+    };
+
+    (metadata.file_attributes() & ATTR_HIDDEN) > 0
+}
+
+#[cfg(not(windows))]
+fn is_hidden(entry: &DirEntry) -> bool {
+    match entry.file_name().to_str() {
+        Some(name) => name.starts_with('.'),
+        None => false,
+    }
+}
+
+/// Metadata of a directory entry and whether it is a symlink, or `None` when it is neither a
+/// directory nor a symlink to one. Only symlinks are resolved, everything else uses `lstat`.
+/// Entries that cannot be looked at are added to `errors`, except dangling symlinks.
+fn child_dir_metadata(entry: &DirEntry, errors: &ErrorLog) -> Option<(Metadata, bool)> {
+    let file_type = match entry.file_type() {
+        Ok(file_type) => file_type,
+        Err(error) => {
+            errors.push(&entry.path(), &error);
+            return None;
+        },
+    };
+
+    if file_type.is_symlink() {
         let metadata = match fs::metadata(entry.path()) {
             Ok(metadata) => metadata,
-            Err(_) => return false,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => return None,
+            Err(error) => {
+                errors.push(&entry.path(), &error);
+                return None;
+            },
+        };
+        return match metadata.is_dir() {
+            true => Some((metadata, true)),
+            false => None,
         };
+    }
+
+    if !file_type.is_dir() {
+        return None;
+    }
+
+    match entry.metadata() {
+        Ok(metadata) => Some((metadata, false)),
+        Err(error) => {
+            errors.push(&entry.path(), &error);
+            None
+        },
+    }
+}
 
-        let attributes = metadata.file_attributes();
+fn is_skipped_mount(path: &Path, is_link: bool, skipped_mounts: &SkippedMounts) -> bool {
+    if !is_link {
+        return skipped_mounts.contains(path);
+    }
 
-        (attributes & ATTR_HIDDEN) > 0
-    } else {
-        false
+    match fs::canonicalize(path) {
+        Ok(path) => skipped_mounts.contains(&path),
+        Err(_) => true,
     }
 }
 
-fn spawn_search_file(entry: DirEntry, set: &mut JoinSet<Vec<DirEntry>>) {
-    set.spawn(async move {
-        search_folder(entry.path().as_path()).await
-    });
+fn spawn_search_file(dir: SearchDir, option: Arc<SearchOption>, tx: Sender<TargetDir>, group: &TaskGroup) {
+    group.spawn(move |group| search_folder(dir, option, tx, group));
 }
 
-async fn search_folder(path: &Path) -> Vec<DirEntry> {
-    let entries = match path.read_dir() {
-        Ok(entries) => entries,
-        Err(_) => return vec![],
+fn search_folder(dir: SearchDir, option: Arc<SearchOption>, tx: Sender<TargetDir>, group: &TaskGroup) {
+    if option.max_depth.map(|max_depth| dir.depth >= max_depth).unwrap_or(false) {
+        return;
+    }
+
+    // Watching first, so a directory created while this one is read is reported either way.
+    if let Some(watcher) = &dir.watcher {
+        if let Err(error) = watcher.add(&dir.path) {
+            dir.errors.push(&dir.path, &error);
+        }
+    }
+
+    let (entries, _) = {
+        let _permit = group.pool().open_dir();
+        dir.errors.read_dir(&dir.path)
     };
 
-    let child_dir: Vec<DirEntry> = entries.filter_map(|entry| entry.ok()).filter(|entry| entry.path().is_dir() && !is_hidden(entry)).collect();
+    let rules = dir.rules.child(&dir.path);
 
-    let mut ignore_dir: Option<usize> = None;
-    let mut result: Vec<DirEntry> = vec![];
-    if let Some(dir_index) = child_dir.iter().position(|dir| is_match_name_dir(dir)) {
-        ignore_dir = Some(dir_index);
+    for entry in entries {
+        if group.is_cancelled() || !search_entry(&dir, &rules, entry, &option, &tx, group) {
+            return;
+        }
     }
+}
 
-    let mut set: JoinSet<Vec<DirEntry>> = JoinSet::new();
+/// Report `entry` of `dir` when it is a target directory, otherwise queue it to be searched.
+/// Returns `false` once nobody listens to the results anymore.
+fn search_entry(dir: &SearchDir, rules: &Arc<IgnoreRules>, entry: DirEntry, option: &Arc<SearchOption>, tx: &Sender<TargetDir>, group: &TaskGroup) -> bool {
+    if is_quarantined(&entry.file_name()) {
+        return true;
+    }
 
-    for (index, entry) in child_dir.into_iter().enumerate() {
-        match ignore_dir {
-            Some(ignore_index) => {
-                if ignore_index == index {
-                    result.push(entry);
-                    continue;
-                }
-            },
-            _ => {}
+    let (metadata, is_link) = match child_dir_metadata(&entry, &dir.errors) {
+        Some(result) => result,
+        None => return true,
+    };
+
+    let path = entry.path();
+    if rules.is_ignored(&path) {
+        return true;
+    }
+
+    // Checked before profiles too, so a target that is itself a foreign mount is not listed.
+    if option.one_file_system && dir.root_device.is_some() && device_id(&metadata) != dir.root_device {
+        return true;
+    }
+
+    if is_skipped_mount(&path, is_link, &option.skipped_mounts) {
+        return true;
+    }
+
+    // Target directories are reported even when hidden (e.g. `.venv`), but never searched into.
+    if let Some(profile) = option.profiles.find(&entry, &dir.errors) {
+        let profile = profile.name.clone();
+        return tx.blocking_send(TargetDir { root: dir.root.clone(), entry, profile, is_link }).is_ok();
+    }
+
+    if !option.include_hidden && is_hidden(&entry) {
+        return true;
+    }
+
+    if is_link && !option.follow_links {
+        return true;
+    }
+
+    let depth = dir.depth + 1;
+    if option.max_depth.map(|max_depth| depth >= max_depth).unwrap_or(false) {
+        return true;
+    }
+
+    if !dir.visited.insert(&path, &metadata) {
+        return true;
+    }
+
+    let child = SearchDir {
+        root: dir.root.clone(),
+        root_device: dir.root_device,
+        path,
+        depth,
+        rules: rules.clone(),
+        visited: dir.visited.clone(),
+        watcher: dir.watcher.clone(),
+        errors: dir.errors.clone(),
+    };
+    spawn_search_file(child, option.clone(), tx.clone(), group);
+
+    true
+}
+
+/// Drop duplicated roots and roots nested inside another root, keeping the input order.
+fn dedupe_roots(roots: &[PathBuf]) -> Vec<PathBuf> {
+    let mut result: Vec<PathBuf> = vec![];
+
+    for root in roots {
+        let is_covered = roots.iter().any(|other| other != root && root.starts_with(other));
+        if !is_covered && !result.contains(root) {
+            result.push(root.clone());
         }
-        spawn_search_file(entry,&mut set);
     }
 
-    while let Some(data) = set.join_next().await {
-        let child_result = data.unwrap_or(Vec::new());
-        result.extend(child_result);
+    result
+}
+
+/// Search every root, sending each target directory through `tx` as soon as it is found.
+/// Every searched directory is added to `watcher` when one is given, and every directory that
+/// cannot be read to `errors`.
+pub async fn get_files_path(pool: WorkerPool, option: Arc<SearchOption>, cancel: CancelToken, watcher: Option<Watcher>, errors: ErrorLog, tx: Sender<TargetDir>) {
+    pool.run(cancel, move |group| search_roots(option, watcher, errors, tx, group)).await
+}
+
+fn search_roots(option: Arc<SearchOption>, watcher: Option<Watcher>, errors: ErrorLog, tx: Sender<TargetDir>, group: &TaskGroup) {
+    let visited = VisitedDirs::default();
+
+    for root in dedupe_roots(&option.roots) {
+        let mut root_device = None;
+        if let Ok(metadata) = fs::metadata(&root) {
+            visited.insert(&root, &metadata);
+            root_device = device_id(&metadata);
+        }
+
+        let rules = IgnoreRules::new(&root, &option.excludes);
+        let dir = SearchDir {
+            root: Arc::from(root.as_path()),
+            root_device,
+            path: root,
+            depth: 0,
+            rules,
+            visited: visited.clone(),
+            watcher: watcher.clone(),
+            errors: errors.clone(),
+        };
+        spawn_search_file(dir, option.clone(), tx.clone(), group);
+    }
+}
+
+/// The ignore rules the search collects on its way from `root` down to the entries of `dir`.
+pub fn ignore_rules_for(root: &Path, dir: &Path, excludes: &[String]) -> Arc<IgnoreRules> {
+    let relative = dir.strip_prefix(root).unwrap_or(dir);
+    let mut rules = IgnoreRules::new(root, excludes).child(root);
+    let mut current = root.to_path_buf();
+    for component in relative.components() {
+        current.push(component);
+        rules = rules.child(&current);
     }
 
-    return result;
+    rules
 }
 
-pub async fn get_files_path() -> Vec<DirEntry> {
-    let current_directory = env::current_dir().expect("Cannot get current directory");
-    return search_folder(&current_directory).await
-}
\ No newline at end of file
+/// Search a directory that appeared after the initial search, as if it had been found by it.
+pub async fn search_new_dir(pool: WorkerPool, option: Arc<SearchOption>, cancel: CancelToken, watcher: Option<Watcher>, errors: ErrorLog, path: PathBuf, tx: Sender<TargetDir>) {
+    pool.run(cancel, move |group| search_new_entry(option, watcher, errors, path, tx, group)).await
+}
+
+fn search_new_entry(option: Arc<SearchOption>, watcher: Option<Watcher>, errors: ErrorLog, path: PathBuf, tx: Sender<TargetDir>, group: &TaskGroup) {
+    let (parent, name) = match (path.parent(), path.file_name()) {
+        (Some(parent), Some(name)) => (parent, name),
+        _ => return,
+    };
+
+    let roots = dedupe_roots(&option.roots);
+    let root = match roots.iter().find(|root| parent.starts_with(root)) {
+        Some(root) => root,
+        None => return,
+    };
+
+    let entry = match parent.read_dir() {
+        Ok(entries) => entries.filter_map(|entry| entry.ok()).find(|entry| entry.file_name() == name),
+        Err(_) => return,
+    };
+    let entry = match entry {
+        Some(entry) => entry,
+        None => return,
+    };
+
+    let rules = ignore_rules_for(root, parent, &option.excludes);
+    let depth = parent.strip_prefix(root).unwrap_or(parent).components().count();
+    if option.max_depth.map(|max_depth| depth >= max_depth).unwrap_or(false) {
+        return;
+    }
+
+    let dir = SearchDir {
+        root: Arc::from(root.as_path()),
+        root_device: fs::metadata(root).ok().and_then(|metadata| device_id(&metadata)),
+        path: parent.to_path_buf(),
+        depth,
+        rules: rules.clone(),
+        visited: VisitedDirs::default(),
+        watcher,
+        errors,
+    };
+    search_entry(&dir, &rules, entry, &option, &tx, group);
+}
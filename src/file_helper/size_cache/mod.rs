@@ -0,0 +1,136 @@
+use std::{collections::HashMap, fs::{self, Metadata}, io, path::{Path, PathBuf}, time::UNIX_EPOCH};
+
+use super::DirStats;
+
+/// Identity of a sized directory. A cached size is only used while the directory is the same
+/// one, not modified since; changes deeper down are caught by revalidating in the background.
+#[derive(PartialEq)]
+struct CacheKey {
+    inode: u64,
+    modified: u128,
+}
+
+#[cfg(unix)]
+fn inode(metadata: &Metadata) -> u64 {
+    use std::os::unix::fs::MetadataExt;
+
+    metadata.ino()
+}
+
+#[cfg(not(unix))]
+fn inode(_metadata: &Metadata) -> u64 {
+    0
+}
+
+impl CacheKey {
+    fn read(path: &Path) -> Option<CacheKey> {
+        let metadata = fs::symlink_metadata(path).ok()?;
+        let modified = metadata.modified().ok()?.duration_since(UNIX_EPOCH).ok()?;
+
+        Some(CacheKey { inode: inode(&metadata), modified: modified.as_nanos() })
+    }
+}
+
+struct CacheEntry {
+    key: CacheKey,
+    size: DirStats,
+}
+
+impl CacheEntry {
+    /// `path inode modified apparent reclaimable files packages`, tab separated.
+    fn parse(line: &str) -> Option<(PathBuf, CacheEntry)> {
+        let mut fields = line.split('\t');
+        let path = PathBuf::from(fields.next()?);
+        let key = CacheKey { inode: fields.next()?.parse().ok()?, modified: fields.next()?.parse().ok()? };
+        let size = DirStats {
+            apparent: fields.next()?.parse().ok()?,
+            reclaimable: fields.next()?.parse().ok()?,
+            files: fields.next()?.parse().ok()?,
+            packages: fields.next()?.parse().ok()?,
+            errors: 0,
+        };
+
+        Some((path, CacheEntry { key, size }))
+    }
+
+    fn line(&self, path: &str) -> String {
+        format!(
+            "{}\t{}\t{}\t{}\t{}\t{}\t{}\n",
+            path, self.key.inode, self.key.modified, self.size.apparent, self.size.reclaimable, self.size.files, self.size.packages
+        )
+    }
+}
+
+/// Sizes of target directories from previous runs, stored one per line in `file`. Only sizes
+/// measured without errors are worth caching.
+pub struct SizeCache {
+    file: PathBuf,
+    entries: HashMap<PathBuf, CacheEntry>,
+}
+
+impl SizeCache {
+    /// Read the cache from `file`, starting empty when it is missing or unreadable.
+    pub fn load(file: PathBuf) -> SizeCache {
+        let entries = match fs::read_to_string(&file) {
+            Ok(content) => content.lines().filter_map(CacheEntry::parse).collect(),
+            Err(_) => HashMap::new(),
+        };
+
+        SizeCache { file, entries }
+    }
+
+    /// Start empty without touching the cache file.
+    pub fn empty(file: PathBuf) -> SizeCache {
+        SizeCache { file, entries: HashMap::new() }
+    }
+
+    /// Remove the cache file and start empty.
+    pub fn clear(file: PathBuf) -> SizeCache {
+        let _ = fs::remove_file(&file);
+        SizeCache::empty(file)
+    }
+
+    /// Cached size of `path`, unless the directory was replaced or modified since.
+    pub fn get(&self, path: &Path) -> Option<DirStats> {
+        let entry = self.entries.get(path)?;
+        match CacheKey::read(path) {
+            Some(key) if key == entry.key => Some(entry.size),
+            _ => None,
+        }
+    }
+
+    pub fn insert(&mut self, path: &Path, size: DirStats) {
+        if let Some(key) = CacheKey::read(path) {
+            self.entries.insert(path.to_path_buf(), CacheEntry { key, size });
+        }
+    }
+
+    pub fn remove(&mut self, path: &Path) {
+        self.entries.remove(path);
+    }
+
+    /// Write the entries of directories that still exist back to the cache file.
+    pub fn save(&self) -> io::Result<()> {
+        let mut content = String::new();
+        for (path, entry) in &self.entries {
+            // Paths that cannot be written on a single line are not worth a cache entry.
+            let path_text = match path.to_str() {
+                Some(text) if !text.contains(['\t', '\n', '\r']) => text,
+                _ => continue,
+            };
+
+            if path.exists() {
+                content.push_str(&entry.line(path_text));
+            }
+        }
+
+        if let Some(dir) = self.file.parent() {
+            fs::create_dir_all(dir)?;
+        }
+
+        // Write next to the cache file first, so a crash never leaves it half written.
+        let temp = self.file.with_extension("tmp");
+        fs::write(&temp, content)?;
+        fs::rename(&temp, &self.file)
+    }
+}
@@ -0,0 +1,152 @@
+use std::{fs::{self, DirEntry}, io, path::Path};
+
+use super::ErrorLog;
+
+/// `(name, directory names, manifests)`. A manifest starting with `/` must exist inside the
+/// target directory, any other manifest must exist next to it. `*.ext` matches any file with
+/// that extension.
+const BUILT_IN: [(&str, &[&str], &[&str]); 9] = [
+    ("node", &["node_modules"], &["package.json"]),
+    ("rust", &["target"], &["Cargo.toml"]),
+    ("python", &[".venv", "venv"], &["/pyvenv.cfg"]),
+    ("pycache", &["__pycache__"], &["*.py"]),
+    ("gradle", &["build", ".gradle"], &["build.gradle", "build.gradle.kts", "settings.gradle", "settings.gradle.kts"]),
+    ("next", &[".next"], &["package.json"]),
+    ("dist", &["dist"], &["package.json"]),
+    ("vendor", &["vendor"], &["composer.json"]),
+    ("pods", &["Pods"], &["Podfile"]),
+];
+
+#[derive(Clone)]
+pub enum Manifest {
+    Sibling(String),
+    Inner(String),
+}
+
+impl Manifest {
+    fn parse(value: &str) -> Manifest {
+        match value.strip_prefix('/') {
+            Some(name) => Manifest::Inner(name.to_owned()),
+            None => Manifest::Sibling(value.to_owned()),
+        }
+    }
+
+    fn exists(&self, dir: &Path, errors: &ErrorLog) -> bool {
+        let (base, name) = match self {
+            Manifest::Inner(name) => (dir, name),
+            Manifest::Sibling(name) => match dir.parent() {
+                Some(parent) => (parent, name),
+                None => return false,
+            },
+        };
+
+        match name.strip_prefix("*.") {
+            Some(extension) => has_file_with_extension(base, extension, errors),
+            None => is_file(&base.join(name), errors),
+        }
+    }
+}
+
+/// Whether `path` is a file; failing to tell for another reason than it missing is logged.
+fn is_file(path: &Path, errors: &ErrorLog) -> bool {
+    match fs::metadata(path) {
+        Ok(metadata) => metadata.is_file(),
+        Err(error) if error.kind() == io::ErrorKind::NotFound => false,
+        Err(error) => {
+            errors.push(path, &error);
+            false
+        },
+    }
+}
+
+fn has_file_with_extension(dir: &Path, extension: &str, errors: &ErrorLog) -> bool {
+    let (entries, _) = errors.read_dir(dir);
+
+    entries
+        .into_iter()
+        .any(|entry| entry.path().extension().map(|ext| ext == extension).unwrap_or(false) && is_file(&entry.path(), errors))
+}
+
+#[derive(Clone)]
+pub struct TargetProfile {
+    pub name: String,
+    pub dir_names: Vec<String>,
+    pub manifests: Vec<Manifest>,
+}
+
+impl TargetProfile {
+    pub fn new(name: &str, dir_names: &[&str], manifests: &[&str]) -> TargetProfile {
+        TargetProfile {
+            name: name.to_owned(),
+            dir_names: dir_names.iter().map(|dir| dir.to_string()).collect(),
+            manifests: manifests.iter().map(|manifest| Manifest::parse(manifest)).collect(),
+        }
+    }
+
+    /// Parse a user-defined profile written as `name:dir[,dir...][:manifest[,manifest...]]`.
+    pub fn parse(value: &str) -> Option<TargetProfile> {
+        let mut parts = value.splitn(3, ':');
+        let name = parts.next()?.trim();
+        let dir_names = split_names(parts.next()?);
+        let manifests = parts.next().map(split_names).unwrap_or_default();
+
+        if name.is_empty() || dir_names.is_empty() {
+            return None;
+        }
+
+        Some(TargetProfile {
+            name: name.to_owned(),
+            dir_names,
+            manifests: manifests.iter().map(|manifest| Manifest::parse(manifest)).collect(),
+        })
+    }
+
+    pub fn built_in() -> Vec<TargetProfile> {
+        BUILT_IN.iter().map(|(name, dirs, manifests)| TargetProfile::new(name, dirs, manifests)).collect()
+    }
+
+    fn is_match(&self, dir: &DirEntry, errors: &ErrorLog) -> bool {
+        let file_name = dir.file_name();
+        let file_name = match file_name.to_str() {
+            Some(name) => name,
+            None => return false,
+        };
+
+        if !self.dir_names.iter().any(|name| name == file_name) {
+            return false;
+        }
+
+        // A profile without manifests trusts the directory name alone.
+        let path = dir.path();
+        self.manifests.is_empty() || self.manifests.iter().any(|manifest| manifest.exists(&path, errors))
+    }
+}
+
+fn split_names(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(|name| name.trim().to_owned())
+        .filter(|name| !name.is_empty())
+        .collect()
+}
+
+#[derive(Clone, Default)]
+pub struct TargetProfiles {
+    profiles: Vec<TargetProfile>,
+}
+
+impl TargetProfiles {
+    pub fn new(profiles: Vec<TargetProfile>) -> TargetProfiles {
+        TargetProfiles { profiles }
+    }
+
+    pub fn is_target_name(&self, name: &str) -> bool {
+        self.profiles.iter().any(|profile| profile.dir_names.iter().any(|dir_name| dir_name == name))
+    }
+
+    /// The profile `dir` is a target directory of. Manifests that cannot be checked are added
+    /// to `errors`.
+    pub fn find(&self, dir: &DirEntry, errors: &ErrorLog) -> Option<&TargetProfile> {
+        self.profiles.iter().find(|profile| profile.is_match(dir, errors))
+    }
+}
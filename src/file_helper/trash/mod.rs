@@ -0,0 +1,214 @@
+use std::{io, path::Path};
+
+#[cfg(unix)]
+mod inner {
+    use std::{env, ffi::OsStr, fs::{self, OpenOptions}, io::{self, Write}, os::unix::{ffi::OsStrExt, fs::{DirBuilderExt, MetadataExt, PermissionsExt}}, path::{Path, PathBuf}};
+
+    const STICKY_BIT: u32 = 0o1000;
+
+    fn uid() -> u32 {
+        unsafe { libc::getuid() }
+    }
+
+    fn home_trash() -> Option<PathBuf> {
+        let data_home = match env::var_os("XDG_DATA_HOME") {
+            Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+            _ => PathBuf::from(env::var_os("HOME")?).join(".local").join("share"),
+        };
+
+        Some(data_home.join("Trash"))
+    }
+
+    /// The mount point `path` is on: its highest ancestor on the same device.
+    fn top_dir(path: &Path, device: u64) -> PathBuf {
+        let mut top = path.to_path_buf();
+        for ancestor in path.ancestors().skip(1) {
+            match fs::metadata(ancestor) {
+                Ok(metadata) if metadata.dev() == device => top = ancestor.to_path_buf(),
+                _ => break,
+            }
+        }
+        top
+    }
+
+    fn create_private_dir(path: &Path) -> io::Result<()> {
+        fs::DirBuilder::new().recursive(true).mode(0o700).create(path)
+    }
+
+    /// `$topdir/.Trash/$uid` when the administrator set up a shared trash, `$topdir/.Trash-$uid`
+    /// otherwise.
+    fn mount_trash(top_dir: &Path) -> io::Result<PathBuf> {
+        let shared = top_dir.join(".Trash");
+        if let Ok(metadata) = fs::symlink_metadata(&shared) {
+            if metadata.is_dir() && metadata.permissions().mode() & STICKY_BIT != 0 {
+                let trash = shared.join(uid().to_string());
+                if create_private_dir(&trash).is_ok() {
+                    return Ok(trash);
+                }
+            }
+        }
+
+        let trash = top_dir.join(format!(".Trash-{}", uid()));
+        create_private_dir(&trash)?;
+        Ok(trash)
+    }
+
+    /// Device of `path`, or of its closest existing ancestor when it does not exist yet.
+    fn device_of(path: &Path) -> Option<u64> {
+        path.ancestors().find_map(|ancestor| fs::metadata(ancestor).ok()).map(|metadata| metadata.dev())
+    }
+
+    /// The trash directory for `path` and the directory the paths in its info files are
+    /// relative to, if any.
+    fn trash_for(path: &Path) -> io::Result<(PathBuf, Option<PathBuf>)> {
+        let device = fs::symlink_metadata(path)?.dev();
+
+        // The home trash is only created when it can take `path`, a read-only or foreign
+        // home falls back to the trash of the mount.
+        if let Some(home_trash) = home_trash() {
+            if device_of(&home_trash) == Some(device) && create_private_dir(&home_trash).is_ok() {
+                return Ok((home_trash, None));
+            }
+        }
+
+        let parent = path.parent().unwrap_or(path);
+        let top_dir = top_dir(parent, device);
+        Ok((mount_trash(&top_dir)?, Some(top_dir)))
+    }
+
+    fn percent_encode(path: &Path) -> String {
+        let mut encoded = String::new();
+        for byte in path.as_os_str().as_bytes() {
+            match byte {
+                b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b'/' => encoded.push(*byte as char),
+                _ => encoded.push_str(&format!("%{:02X}", byte)),
+            }
+        }
+        encoded
+    }
+
+    fn trash_info(original: &Path, deletion_date: &str) -> String {
+        format!("[Trash Info]\nPath={}\nDeletionDate={}\n", percent_encode(original), deletion_date)
+    }
+
+    /// The local time as `YYYY-MM-DDThh:mm:ss`.
+    fn deletion_date() -> String {
+        let tm = unsafe {
+            let now = libc::time(std::ptr::null_mut());
+            let mut tm: libc::tm = std::mem::zeroed();
+            libc::localtime_r(&now, &mut tm);
+            tm
+        };
+
+        format!(
+            "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}",
+            tm.tm_year + 1900, tm.tm_mon + 1, tm.tm_mday, tm.tm_hour, tm.tm_min, tm.tm_sec
+        )
+    }
+
+    /// Create the info file under a name no other trashed file uses, returning that name.
+    fn claim_info_file(info_dir: &Path, name: &OsStr, info: &str) -> io::Result<(PathBuf, String)> {
+        let name = name.to_string_lossy();
+        let mut attempt = 1;
+
+        loop {
+            let candidate = match attempt {
+                1 => name.to_string(),
+                _ => format!("{}.{}", name, attempt),
+            };
+
+            let info_path = info_dir.join(format!("{}.trashinfo", candidate));
+            match OpenOptions::new().write(true).create_new(true).open(&info_path) {
+                Ok(mut file) => {
+                    file.write_all(info.as_bytes())?;
+                    return Ok((info_path, candidate));
+                },
+                Err(error) if error.kind() == io::ErrorKind::AlreadyExists => attempt += 1,
+                Err(error) => return Err(error),
+            }
+        }
+    }
+
+    pub fn move_to_trash(path: &Path) -> io::Result<()> {
+        let name = path.file_name().ok_or_else(|| io::Error::from(io::ErrorKind::InvalidInput))?;
+        let (trash, relative_to) = trash_for(path)?;
+
+        let files_dir = trash.join("files");
+        let info_dir = trash.join("info");
+        create_private_dir(&files_dir)?;
+        create_private_dir(&info_dir)?;
+
+        let original = match &relative_to {
+            Some(top_dir) => path.strip_prefix(top_dir).unwrap_or(path),
+            None => path,
+        };
+        let info = trash_info(original, &deletion_date());
+
+        let (info_path, trashed_name) = claim_info_file(&info_dir, name, &info)?;
+        if let Err(error) = fs::rename(path, files_dir.join(trashed_name)) {
+            let _ = fs::remove_file(info_path);
+            return Err(error);
+        }
+
+        Ok(())
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn percent_encode_keeps_unreserved_characters_and_slashes() {
+            assert_eq!(percent_encode(Path::new("/home/me/my-app_1.0~/node_modules")), "/home/me/my-app_1.0~/node_modules");
+        }
+
+        #[test]
+        fn percent_encode_escapes_every_other_byte() {
+            assert_eq!(percent_encode(Path::new("/a b/%/é")), "/a%20b/%25/%C3%A9");
+            assert_eq!(percent_encode(Path::new(OsStr::from_bytes(b"/\xff"))), "/%FF");
+        }
+
+        #[test]
+        fn trash_info_lists_the_encoded_path_and_the_date() {
+            let info = trash_info(Path::new("/tmp/my project/node_modules"), "2024-01-02T03:04:05");
+            assert_eq!(info, "[Trash Info]\nPath=/tmp/my%20project/node_modules\nDeletionDate=2024-01-02T03:04:05\n");
+        }
+
+        #[test]
+        fn deletion_date_has_the_spec_format() {
+            let date = deletion_date();
+            assert_eq!(date.len(), 19);
+            assert_eq!(date.as_bytes()[10], b'T');
+        }
+
+        #[test]
+        fn info_files_of_the_same_name_get_numbered() {
+            let dir = env::temp_dir().join(format!("rkm-trash-test-{}", std::process::id()));
+            fs::create_dir_all(&dir).unwrap();
+
+            let (first, first_name) = claim_info_file(&dir, OsStr::new("node_modules"), "a").unwrap();
+            let (second, second_name) = claim_info_file(&dir, OsStr::new("node_modules"), "b").unwrap();
+            assert_eq!(first_name, "node_modules");
+            assert_eq!(second_name, "node_modules.2");
+            assert_eq!(fs::read_to_string(first).unwrap(), "a");
+            assert_eq!(fs::read_to_string(second).unwrap(), "b");
+
+            fs::remove_dir_all(&dir).unwrap();
+        }
+    }
+}
+
+#[cfg(not(unix))]
+mod inner {
+    use std::{io, path::Path};
+
+    pub fn move_to_trash(_path: &Path) -> io::Result<()> {
+        Err(io::Error::from(io::ErrorKind::Unsupported))
+    }
+}
+
+/// Move `path` to the trash of the current user following the freedesktop.org Trash
+/// specification, so it can be restored from any file manager.
+pub fn move_to_trash(path: &Path) -> io::Result<()> {
+    inner::move_to_trash(path)
+}
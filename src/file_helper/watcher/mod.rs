@@ -0,0 +1,182 @@
+use std::{io, path::{Path, PathBuf}};
+
+use tokio::sync::mpsc::Receiver;
+
+use super::CancelToken;
+
+pub enum WatchEvent {
+    Created(PathBuf),
+    Removed(PathBuf),
+}
+
+#[cfg(target_os = "linux")]
+mod inotify {
+    use std::{collections::HashMap, ffi::CString, io, os::unix::ffi::OsStrExt, path::{Path, PathBuf}, sync::{Arc, Mutex}, thread};
+
+    use tokio::sync::mpsc::{self, Receiver, Sender};
+
+    use super::{CancelToken, WatchEvent};
+
+    const WATCH_BUFFER: usize = 100;
+    const POLL_TIMEOUT_MS: i32 = 200;
+    const EVENT_HEADER: usize = 16;
+    const WATCH_MASK: u32 = libc::IN_CREATE | libc::IN_MOVED_TO | libc::IN_DELETE | libc::IN_MOVED_FROM | libc::IN_ONLYDIR;
+
+    struct Inotify {
+        fd: i32,
+        watches: Mutex<HashMap<i32, PathBuf>>,
+    }
+
+    impl Drop for Inotify {
+        fn drop(&mut self) {
+            unsafe { libc::close(self.fd) };
+        }
+    }
+
+    #[derive(Clone)]
+    pub struct Watcher {
+        inotify: Arc<Inotify>,
+    }
+
+    impl Watcher {
+        pub fn new(cancel: CancelToken) -> Option<(Watcher, Receiver<WatchEvent>)> {
+            let fd = unsafe { libc::inotify_init1(libc::IN_CLOEXEC | libc::IN_NONBLOCK) };
+            if fd < 0 {
+                return None;
+            }
+
+            let inotify = Arc::new(Inotify { fd, watches: Mutex::new(HashMap::new()) });
+            let (tx, rx) = mpsc::channel(WATCH_BUFFER);
+
+            let thread_inotify = inotify.clone();
+            thread::spawn(move || read_events(thread_inotify, cancel, tx));
+
+            Some((Watcher { inotify }, rx))
+        }
+
+        pub fn add(&self, path: &Path) -> io::Result<()> {
+            let c_path = CString::new(path.as_os_str().as_bytes()).map_err(|error| io::Error::new(io::ErrorKind::InvalidInput, error))?;
+
+            let wd = unsafe { libc::inotify_add_watch(self.inotify.fd, c_path.as_ptr(), WATCH_MASK) };
+            if wd < 0 {
+                return Err(io::Error::last_os_error());
+            }
+
+            if let Ok(mut watches) = self.inotify.watches.lock() {
+                watches.insert(wd, path.to_path_buf());
+            }
+            Ok(())
+        }
+    }
+
+    fn read_u32(buffer: &[u8], offset: usize) -> u32 {
+        let mut bytes = [0u8; 4];
+        bytes.copy_from_slice(&buffer[offset..offset + 4]);
+        u32::from_ne_bytes(bytes)
+    }
+
+    /// Turn the raw `inotify_event` records of `buffer` into watch events for directories.
+    fn parse_events(inotify: &Inotify, buffer: &[u8]) -> Vec<WatchEvent> {
+        let mut events = vec![];
+        let mut offset = 0;
+        let mut watches = match inotify.watches.lock() {
+            Ok(watches) => watches,
+            Err(_) => return events,
+        };
+
+        while offset + EVENT_HEADER <= buffer.len() {
+            let wd = read_u32(buffer, offset) as i32;
+            let mask = read_u32(buffer, offset + 4);
+            let name_len = read_u32(buffer, offset + 12) as usize;
+            let name_bytes = &buffer[offset + EVENT_HEADER..(offset + EVENT_HEADER + name_len).min(buffer.len())];
+            offset += EVENT_HEADER + name_len;
+
+            if mask & libc::IN_IGNORED != 0 {
+                watches.remove(&wd);
+                continue;
+            }
+
+            if mask & libc::IN_ISDIR == 0 {
+                continue;
+            }
+
+            let name = name_bytes.split(|byte| *byte == 0).next().unwrap_or_default();
+            let path = match watches.get(&wd) {
+                Some(dir) => dir.join(std::ffi::OsStr::from_bytes(name)),
+                None => continue,
+            };
+
+            if mask & (libc::IN_CREATE | libc::IN_MOVED_TO) != 0 {
+                events.push(WatchEvent::Created(path));
+            } else if mask & (libc::IN_DELETE | libc::IN_MOVED_FROM) != 0 {
+                events.push(WatchEvent::Removed(path));
+            }
+        }
+
+        events
+    }
+
+    fn read_events(inotify: Arc<Inotify>, cancel: CancelToken, tx: Sender<WatchEvent>) {
+        let mut buffer = [0u8; 4096];
+
+        while !cancel.is_cancelled() && !tx.is_closed() {
+            let mut poll_fd = libc::pollfd { fd: inotify.fd, events: libc::POLLIN, revents: 0 };
+            if unsafe { libc::poll(&mut poll_fd, 1, POLL_TIMEOUT_MS) } <= 0 {
+                continue;
+            }
+
+            let read = unsafe { libc::read(inotify.fd, buffer.as_mut_ptr() as *mut libc::c_void, buffer.len()) };
+            if read <= 0 {
+                continue;
+            }
+
+            for event in parse_events(&inotify, &buffer[..read as usize]) {
+                if tx.blocking_send(event).is_err() {
+                    return;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+mod inotify {
+    use std::{io, path::Path};
+
+    use tokio::sync::mpsc::Receiver;
+
+    use super::{CancelToken, WatchEvent};
+
+    /// Watching relies on inotify, so it is unavailable on other platforms.
+    #[derive(Clone)]
+    pub struct Watcher;
+
+    impl Watcher {
+        pub fn new(_cancel: CancelToken) -> Option<(Watcher, Receiver<WatchEvent>)> {
+            None
+        }
+
+        pub fn add(&self, _path: &Path) -> io::Result<()> {
+            Ok(())
+        }
+    }
+}
+
+/// Watches searched directories for target directories being created or removed.
+#[derive(Clone)]
+pub struct Watcher {
+    inner: inotify::Watcher,
+}
+
+impl Watcher {
+    /// Start watching until `cancel` is triggered; `None` when watching is not available.
+    pub fn new(cancel: CancelToken) -> Option<(Watcher, Receiver<WatchEvent>)> {
+        let (inner, rx) = inotify::Watcher::new(cancel)?;
+        Some((Watcher { inner }, rx))
+    }
+
+    /// Fails when the directory cannot be watched, e.g. once the system watch limit is reached.
+    pub fn add(&self, path: &Path) -> io::Result<()> {
+        self.inner.add(path)
+    }
+}
@@ -0,0 +1,203 @@
+use std::{
+    collections::VecDeque,
+    panic::{self, AssertUnwindSafe},
+    sync::{Arc, Condvar, Mutex, atomic::{AtomicBool, AtomicUsize, Ordering}},
+    thread::{self, JoinHandle},
+};
+
+use tokio::sync::oneshot;
+
+use super::CancelToken;
+
+type Job = Box<dyn FnOnce() + Send>;
+
+#[derive(Default)]
+struct JobQueue {
+    jobs: Mutex<VecDeque<Job>>,
+    available: Condvar,
+    closed: AtomicBool,
+}
+
+impl JobQueue {
+    fn push(&self, job: Job) {
+        if let Ok(mut jobs) = self.jobs.lock() {
+            jobs.push_back(job);
+            self.available.notify_one();
+        }
+    }
+
+    /// The next job, or `None` once the queue is closed and empty.
+    fn pop(&self) -> Option<Job> {
+        let mut jobs = self.jobs.lock().ok()?;
+        loop {
+            if let Some(job) = jobs.pop_front() {
+                return Some(job);
+            }
+            if self.closed.load(Ordering::SeqCst) {
+                return None;
+            }
+            jobs = self.available.wait(jobs).ok()?;
+        }
+    }
+
+    fn close(&self) {
+        // Taking the lock makes sure no worker is between its check and its wait.
+        if let Ok(_jobs) = self.jobs.lock() {
+            self.closed.store(true, Ordering::SeqCst);
+            self.available.notify_all();
+        }
+    }
+}
+
+/// Threads of a pool, stopped and joined once the last handle to the pool is dropped.
+struct Workers {
+    queue: Arc<JobQueue>,
+    threads: Vec<JoinHandle<()>>,
+}
+
+impl Drop for Workers {
+    fn drop(&mut self) {
+        self.queue.close();
+
+        let current = thread::current().id();
+        for handle in self.threads.drain(..) {
+            // The last handle can belong to a job, whose thread stops once the job returns.
+            if handle.thread().id() != current {
+                let _ = handle.join();
+            }
+        }
+    }
+}
+
+/// Counting semaphore bounding how many directories are open at the same time.
+struct OpenDirs {
+    available: Mutex<usize>,
+    released: Condvar,
+}
+
+impl OpenDirs {
+    fn acquire(&self) {
+        if let Ok(mut available) = self.available.lock() {
+            while *available == 0 {
+                available = match self.released.wait(available) {
+                    Ok(available) => available,
+                    Err(_) => return,
+                };
+            }
+            *available -= 1;
+        }
+    }
+
+    fn release(&self) {
+        if let Ok(mut available) = self.available.lock() {
+            *available += 1;
+            self.released.notify_one();
+        }
+    }
+}
+
+/// Permit for one open directory, given back when dropped.
+pub struct OpenDirPermit {
+    open_dirs: Arc<OpenDirs>,
+}
+
+impl Drop for OpenDirPermit {
+    fn drop(&mut self) {
+        self.open_dirs.release();
+    }
+}
+
+/// Fixed set of threads running the blocking filesystem work of searches and size calculations.
+#[derive(Clone)]
+pub struct WorkerPool {
+    queue: Arc<JobQueue>,
+    open_dirs: Arc<OpenDirs>,
+    _workers: Arc<Workers>,
+}
+
+impl WorkerPool {
+    pub fn new(threads: usize, open_dirs: usize) -> WorkerPool {
+        let queue = Arc::new(JobQueue::default());
+
+        let threads = (0..threads.max(1)).map(|_| {
+            let queue = queue.clone();
+            thread::spawn(move || {
+                while let Some(job) = queue.pop() {
+                    let _ = panic::catch_unwind(AssertUnwindSafe(job));
+                }
+            })
+        }).collect();
+
+        let workers = Arc::new(Workers { queue: queue.clone(), threads });
+        let open_dirs = Arc::new(OpenDirs { available: Mutex::new(open_dirs.max(1)), released: Condvar::new() });
+        WorkerPool { queue, open_dirs, _workers: workers }
+    }
+
+    /// Block until another directory may be opened; hold the permit while reading it.
+    pub fn open_dir(&self) -> OpenDirPermit {
+        self.open_dirs.acquire();
+        OpenDirPermit { open_dirs: self.open_dirs.clone() }
+    }
+
+    /// Run `job` on the pool and wait until it and every job it spawned have finished.
+    /// Once `cancel` is triggered, jobs still queued are dropped without running.
+    pub async fn run(&self, cancel: CancelToken, job: impl FnOnce(&TaskGroup) + Send + 'static) {
+        let (done_tx, done_rx) = oneshot::channel();
+        let group = TaskGroup {
+            pool: self.clone(),
+            cancel,
+            pending: Arc::new(AtomicUsize::new(0)),
+            done: Arc::new(Mutex::new(Some(done_tx))),
+        };
+
+        group.spawn(job);
+        let _ = done_rx.await;
+    }
+}
+
+/// Jobs belonging to one `WorkerPool::run` call.
+#[derive(Clone)]
+pub struct TaskGroup {
+    pool: WorkerPool,
+    cancel: CancelToken,
+    pending: Arc<AtomicUsize>,
+    done: Arc<Mutex<Option<oneshot::Sender<()>>>>,
+}
+
+impl TaskGroup {
+    pub fn pool(&self) -> &WorkerPool {
+        &self.pool
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancel.is_cancelled()
+    }
+
+    pub fn spawn(&self, job: impl FnOnce(&TaskGroup) + Send + 'static) {
+        self.pending.fetch_add(1, Ordering::SeqCst);
+
+        let pending = PendingJob { group: self.clone() };
+        self.pool.queue.push(Box::new(move || {
+            if !pending.group.is_cancelled() {
+                job(&pending.group);
+            }
+        }));
+    }
+}
+
+/// Marks a job of the group as finished when dropped, even if the job panicked.
+struct PendingJob {
+    group: TaskGroup,
+}
+
+impl Drop for PendingJob {
+    fn drop(&mut self) {
+        if self.group.pending.fetch_sub(1, Ordering::SeqCst) != 1 {
+            return;
+        }
+
+        if let Some(done) = self.group.done.lock().ok().and_then(|mut done| done.take()) {
+            let _ = done.send(());
+        }
+    }
+}
@@ -1,12 +1,41 @@
+mod benchmark;
+mod config;
 mod file_helper;
+mod report;
 mod ui;
+use std::process;
+
+use config::{Config, ConfigError};
 use ui::start_ui;
 
 #[tokio::main]
 async fn main() {
-    if let Ok(size) = start_ui().await {
-        println!("Free: {}", size);
+    let config = match Config::from_args() {
+        Ok(config) => config,
+        Err(ConfigError::Help) => {
+            println!("{}", ConfigError::Help.message());
+            return;
+        },
+        Err(err) => {
+            eprintln!("{}", err.message());
+            process::exit(2);
+        }
+    };
+
+    if let Some(projects) = config.benchmark {
+        benchmark::run(&config, projects).await;
+        return;
+    }
+
+    if config.duplicates {
+        report::run(&config).await;
+        return;
+    }
+
+    if let Ok(summary) = start_ui(config).await {
+        println!("{}", summary);
     } else {
-        println!("Error occurs");
+        eprintln!("Error occurs");
+        process::exit(1);
     }
 }
@@ -0,0 +1,156 @@
+use std::{cmp::Reverse, collections::{HashMap, HashSet}, fs, path::{Path, PathBuf}, sync::{Arc, Mutex}};
+
+use tokio::{sync::mpsc, task::JoinSet};
+
+use crate::{config::Config, file_helper::{allocated_size, file_id, get_files_path, installed_packages, size, ErrorLog, CancelToken, FileId, WorkerPool}};
+
+const NODE_MODULES: &str = "node_modules";
+/// Number of duplicated packages listed, the most wasteful first.
+const REPORT_LIMIT: usize = 30;
+
+/// Every installed copy of one version of a package.
+struct Duplicate {
+    name: String,
+    version: String,
+    copies: Vec<PathBuf>,
+    /// Allocated size of every file and directory of each copy, by file id.
+    files: Vec<HashMap<FileId, u64>>,
+}
+
+impl Duplicate {
+    /// Size of the largest copy, the one a shared store would keep.
+    fn largest(&self) -> u64 {
+        self.files.iter().map(|files| files.values().sum()).max().unwrap_or(0)
+    }
+
+    /// Bytes freed by keeping only the largest copy. Files hard-linked between copies are
+    /// already shared, so they only count once whatever copy they are found in.
+    fn wasted(&self) -> u64 {
+        let shared: HashMap<&FileId, &u64> = self.files.iter().flatten().collect();
+        shared.values().copied().sum::<u64>() - self.largest()
+    }
+}
+
+/// Add every file and directory below `dir` to `files`, without following symlinks.
+fn copy_files(dir: &Path, files: &mut HashMap<FileId, u64>) {
+    let entries = match dir.read_dir() {
+        Ok(entries) => entries,
+        Err(_) => return,
+    };
+
+    for entry in entries.filter_map(|entry| entry.ok()) {
+        let path = entry.path();
+        let metadata = match entry.metadata() {
+            Ok(metadata) => metadata,
+            Err(_) => continue,
+        };
+
+        if let Some(id) = file_id(&path, &metadata) {
+            files.insert(id, allocated_size(&metadata));
+        }
+        if metadata.is_dir() {
+            copy_files(&path, files);
+        }
+    }
+}
+
+/// Files of one copy of a package, measured on the pool.
+async fn measure_copy(pool: WorkerPool, copy: PathBuf) -> HashMap<FileId, u64> {
+    let files = Arc::new(Mutex::new(HashMap::new()));
+    let found = files.clone();
+
+    pool.run(CancelToken::default(), move |_| {
+        let mut files = HashMap::new();
+        if let Ok(metadata) = fs::metadata(&copy) {
+            files.extend(file_id(&copy, &metadata).map(|id| (id, allocated_size(&metadata))));
+        }
+        copy_files(&copy, &mut files);
+
+        if let Ok(mut found) = found.lock() {
+            *found = files;
+        }
+    }).await;
+
+    let files = match files.lock() {
+        Ok(mut files) => std::mem::take(&mut *files),
+        Err(_) => HashMap::new(),
+    };
+    files
+}
+
+async fn find_node_modules(config: &Config) -> Vec<PathBuf> {
+    let (tx, mut rx) = mpsc::channel(100);
+    let option = Arc::new(config.search_option());
+    tokio::spawn(get_files_path(config.worker_pool(), option, CancelToken::default(), None, ErrorLog::default(), tx));
+
+    let mut found = vec![];
+    while let Some(target) = rx.recv().await {
+        if target.entry.file_name() == NODE_MODULES && !target.is_link {
+            found.push(target.entry.path());
+        }
+    }
+
+    found
+}
+
+/// Search the configured roots and list the package versions installed in several
+/// `node_modules`, with the bytes a shared store would save.
+pub async fn run(config: &Config) {
+    let node_modules = find_node_modules(config).await;
+
+    // pnpm links several `node_modules/<package>` to the same copy, which is only counted once.
+    let mut seen = HashSet::new();
+    let mut groups: HashMap<(String, String), Vec<PathBuf>> = HashMap::new();
+    for dir in &node_modules {
+        for package in installed_packages(dir) {
+            let path = fs::canonicalize(&package.path).unwrap_or(package.path);
+            if seen.insert(path.clone()) {
+                groups.entry((package.name, package.version)).or_default().push(path);
+            }
+        }
+    }
+
+    let mut duplicates: Vec<Duplicate> = groups
+        .into_iter()
+        .filter(|(_, copies)| copies.len() > 1)
+        .map(|((name, version), copies)| Duplicate { name, version, copies, files: vec![] })
+        .collect();
+
+    let pool = config.worker_pool();
+    let mut set = JoinSet::new();
+    for (index, duplicate) in duplicates.iter().enumerate() {
+        for copy in duplicate.copies.iter().cloned() {
+            let files = measure_copy(pool.clone(), copy);
+            set.spawn(async move { (index, files.await) });
+        }
+    }
+    while let Some(result) = set.join_next().await {
+        if let Ok((index, files)) = result {
+            duplicates[index].files.push(files);
+        }
+    }
+
+    duplicates.sort_by_cached_key(|duplicate| Reverse(duplicate.wasted()));
+    let total_wasted: u64 = duplicates.iter().map(Duplicate::wasted).sum();
+
+    println!("{} package versions installed more than once across {} node_modules", duplicates.len(), node_modules.len());
+    if duplicates.is_empty() {
+        return;
+    }
+
+    println!("{:>7} {:>12} {:>12}  package", "copies", "size", "wasted");
+    for duplicate in duplicates.iter().take(REPORT_LIMIT) {
+        println!(
+            "{:>7} {:>12} {:>12}  {}@{}",
+            duplicate.copies.len(),
+            size(duplicate.largest().into()),
+            size(duplicate.wasted().into()),
+            duplicate.name,
+            duplicate.version,
+        );
+    }
+    if duplicates.len() > REPORT_LIMIT {
+        println!("  .. and {} more", duplicates.len() - REPORT_LIMIT);
+    }
+    println!("Total wasted: {}", size(total_wasted.into()));
+}
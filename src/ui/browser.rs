@@ -0,0 +1,126 @@
+use std::{cmp::Reverse, path::{Path, PathBuf}};
+
+use tui::{widgets::{Table, Cell, Row, Block, Borders, TableState}, style::{Style, Color, Modifier}, layout::Constraint};
+
+use crate::file_helper::{size, CancelToken, DirStats, ErrorLog};
+
+use super::SizeMode;
+
+pub struct BrowseEntry {
+    pub name: String,
+    pub path: PathBuf,
+    pub is_dir: bool,
+    pub size: Option<DirStats>,
+}
+
+/// The children of one directory opened in the drill-down view, largest first.
+pub struct BrowseLevel {
+    /// Identifies the level in size events, so sizes of a closed level are dropped.
+    pub id: usize,
+    pub path: PathBuf,
+    pub entries: Vec<BrowseEntry>,
+    pub state: TableState,
+    /// Stops sizing the entries once the level is closed.
+    pub cancel: CancelToken,
+}
+
+impl BrowseLevel {
+    /// List `path`, adding what cannot be read to `errors`.
+    pub fn read(id: usize, path: PathBuf, errors: &ErrorLog) -> BrowseLevel {
+        let (entries, _) = errors.read_dir(&path);
+        let mut entries: Vec<BrowseEntry> = entries.into_iter().map(|entry| BrowseEntry {
+            name: entry.file_name().to_string_lossy().into_owned(),
+            path: entry.path(),
+            // A symlink is listed as a file, its size is the link itself.
+            is_dir: entry.file_type().map(|file_type| file_type.is_dir()).unwrap_or(false),
+            size: None,
+        }).collect();
+        entries.sort_by(|a, b| a.name.cmp(&b.name));
+
+        let mut state = TableState::default();
+        if !entries.is_empty() {
+            state.select(Some(0));
+        }
+
+        BrowseLevel { id, path, entries, state, cancel: CancelToken::default() }
+    }
+
+    pub fn selected(&self) -> Option<&BrowseEntry> {
+        self.entries.get(self.state.selected()?)
+    }
+
+    pub fn update_size(&mut self, path: &Path, size: DirStats, size_mode: SizeMode) {
+        if let Some(entry) = self.entries.iter_mut().find(|entry| entry.path == path) {
+            entry.size = Some(size);
+        }
+        self.sort(size_mode);
+    }
+
+    /// Sort by size, entries still being measured last, keeping the cursor on the same entry.
+    pub fn sort(&mut self, size_mode: SizeMode) {
+        let selected = self.selected().map(|entry| entry.path.clone());
+
+        // `Reverse` puts the largest first while `None` stays after every measured entry.
+        self.entries.sort_by_key(|entry| Reverse(entry.size.as_ref().map(|size| size_mode.of(size))));
+
+        if let Some(selected) = selected {
+            let index = self.entries.iter().position(|entry| entry.path == selected);
+            self.state.select(index);
+        }
+    }
+
+    pub fn next(&mut self) {
+        let len = self.entries.len();
+        if len == 0 {
+            return;
+        }
+
+        let i = match self.state.selected() {
+            Some(i) if i + 1 < len => i + 1,
+            _ => 0,
+        };
+        self.state.select(Some(i));
+    }
+
+    pub fn previous(&mut self) {
+        let len = self.entries.len();
+        if len == 0 {
+            return;
+        }
+
+        let i = match self.state.selected() {
+            Some(i) if i > 0 && i < len => i - 1,
+            _ => len - 1,
+        };
+        self.state.select(Some(i));
+    }
+}
+
+pub fn browser_table<'a>(level: &BrowseLevel, size_mode: SizeMode) -> Table<'a> {
+    let rows: Vec<Row> = level.entries.iter().map(|entry| {
+        let name = match entry.is_dir {
+            true => Cell::from(format!("{}/", entry.name)).style(Style::default().fg(Color::LightBlue)),
+            false => Cell::from(entry.name.clone()),
+        };
+
+        let (size_cell, files) = match &entry.size {
+            Some(stats) => (Cell::from(size(size_mode.of(stats))), Cell::from(stats.files.to_string())),
+            None => (Cell::from(".."), Cell::from("..")),
+        };
+
+        Row::new(vec![name, size_cell, files])
+    }).collect();
+
+    Table::new(rows)
+        .header(Row::new(vec!["Name", size_mode.label(), "Files"])
+            .style(Style::default().fg(Color::Cyan))
+            .bottom_margin(1)
+        )
+        .block(Block::default().borders(Borders::ALL).title(level.path.display().to_string()))
+        .highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+        .widths(&[
+            Constraint::Percentage(60),
+            Constraint::Percentage(20),
+            Constraint::Percentage(20),
+        ])
+}
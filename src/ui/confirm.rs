@@ -0,0 +1,63 @@
+use tui::{widgets::{Paragraph, Block, Borders, Wrap}, text::{Spans, Span}, style::{Style, Color, Modifier}, layout::{Rect, Layout, Direction, Constraint}};
+
+use crate::file_helper::size;
+
+/// Number of paths listed in the popup, the rest are only counted.
+const LISTED_PATHS: usize = 8;
+
+/// A rectangle of `percent_x` of the width, centered in `area`, tall enough for `lines`.
+pub fn popup_area(area: Rect, percent_x: u16, lines: u16) -> Rect {
+    let height = (lines + 2).min(area.height);
+    let vertical = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length((area.height - height) / 2),
+            Constraint::Length(height),
+            Constraint::Min(0),
+        ].as_ref())
+        .split(area);
+
+    Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([
+            Constraint::Percentage((100 - percent_x) / 2),
+            Constraint::Percentage(percent_x),
+            Constraint::Percentage((100 - percent_x) / 2),
+        ].as_ref())
+        .split(vertical[1])[1]
+}
+
+pub fn confirm_lines(paths: &[String]) -> u16 {
+    let more = match paths.len() > LISTED_PATHS {
+        true => 1,
+        false => 0,
+    };
+    (paths.len().min(LISTED_PATHS) + more + 3) as u16
+}
+
+/// Ask before deleting `paths`, or moving them to the trash, `total_size` bytes altogether.
+pub fn confirm_popup<'a>(paths: &[String], total_size: u128, trash: bool) -> Paragraph<'a> {
+    let mut lines: Vec<Spans> = paths.iter().take(LISTED_PATHS).map(|path| Spans::from(path.clone())).collect();
+    if paths.len() > LISTED_PATHS {
+        lines.push(Spans::from(format!(".. and {} more", paths.len() - LISTED_PATHS)));
+    }
+
+    lines.push(Spans::from(vec![
+        Span::raw("Total size: "),
+        Span::styled(size(total_size), Style::default().fg(Color::Green).add_modifier(Modifier::BOLD)),
+    ]));
+    lines.push(Spans::from(""));
+    lines.push(Spans::from("Confirm with 'y', any other key cancels"));
+
+    let title = match (paths.len(), trash) {
+        (1, false) => "Delete this directory?".to_owned(),
+        (count, false) => format!("Delete these {} directories?", count),
+        (1, true) => "Move this directory to the trash?".to_owned(),
+        (count, true) => format!("Move these {} directories to the trash?", count),
+    };
+
+    Paragraph::new(lines)
+        .block(Block::default().borders(Borders::ALL).title(Span::styled(title, Style::default().fg(Color::Red).add_modifier(Modifier::BOLD))))
+        .style(Style::default().bg(Color::Black))
+        .wrap(Wrap { trim: false })
+}
@@ -0,0 +1,42 @@
+use tui::{widgets::{Paragraph, Wrap}, text::{Spans, Span}, style::{Style, Color, Modifier}, layout::Alignment};
+
+use crate::file_helper::size;
+
+use super::DirData;
+
+fn detail<'a>(field_name: &str, value: String) -> Spans<'a> {
+    Spans::from(vec![
+        Span::raw(field_name.to_owned()),
+        Span::raw(": "),
+        Span::styled(value, Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD))
+    ])
+}
+
+/// Full path, sizes and counts of the selected row.
+pub fn details_block<'a>(item: Option<&DirData>) -> Paragraph<'a> {
+    let item = match item {
+        Some(item) => item,
+        None => return Paragraph::new("No directory selected").style(Style::default().fg(Color::DarkGray).bg(Color::Black)),
+    };
+
+    let (apparent, reclaimable, files, packages) = match &item.size {
+        Some(stats) if stats.errors > 0 => {
+            let files = format!("{} ({} unreadable)", stats.files, stats.errors);
+            (size(stats.apparent.into()), size(stats.reclaimable.into()), files, stats.packages.to_string())
+        },
+        Some(stats) => (size(stats.apparent.into()), size(stats.reclaimable.into()), stats.files.to_string(), stats.packages.to_string()),
+        None => ("..".to_owned(), "..".to_owned(), "..".to_owned(), "..".to_owned()),
+    };
+
+    let details = vec![
+        detail("Path", item.path.clone()),
+        detail("Apparent size", apparent),
+        detail("Reclaimable", reclaimable),
+        detail("Files", files),
+        detail("Packages", packages),
+    ];
+    Paragraph::new(details)
+        .style(Style::default().bg(Color::Black))
+        .alignment(Alignment::Left)
+        .wrap(Wrap { trim: true })
+}
@@ -0,0 +1,19 @@
+use tui::{widgets::{Paragraph, Block, Borders}, text::{Spans, Span}, style::{Style, Color}};
+
+use crate::file_helper::ScanError;
+
+/// Number of errors shown at once, the latest ones.
+pub const ERROR_LOG_LINES: usize = 4;
+
+pub fn error_log_block<'a>(errors: &[ScanError], total: usize) -> Paragraph<'a> {
+    let lines: Vec<Spans> = errors.iter().map(|error| {
+        Spans::from(vec![
+            Span::styled(error.kind.to_string(), Style::default().fg(Color::Red)),
+            Span::raw(": "),
+            Span::raw(error.path.display().to_string()),
+        ])
+    }).collect();
+
+    Paragraph::new(lines)
+        .block(Block::default().borders(Borders::ALL).title(format!("Errors ({})", total)))
+}
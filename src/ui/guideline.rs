@@ -1,9 +1,19 @@
 use tui::{widgets::Paragraph, style::{Style, Color}};
 
-const GUIDELINE: &'static str = r"Select with CURSORS
-Delete with SPACE
+const GUIDELINE: &str = r"Select with CURSORS
+Delete with SPACE, undo with 'u' until you quit
+Mark with 'm', mark all with '*', invert with 'i', delete marked with 'd'
+Look inside with ENTER, go back with BACKSPACE
+Rescan with 'r'
+Only show idle projects with 'o', mark them with 'O'
+Switch apparent/reclaimable size with 'a'
 Quit with 'q'";
 
+/// Rows needed to show every line of the guideline.
+pub fn guideline_height() -> u16 {
+    GUIDELINE.lines().count() as u16
+}
+
 pub fn guideline<'a>() -> Paragraph<'a> {
     Paragraph::new(GUIDELINE).style(Style::default().bg(Color::Yellow).fg(Color::Black))
 }
\ No newline at end of file
@@ -7,6 +7,19 @@ pub enum InputEventType {
     Up,
     Down,
     Select,
+    Open,
+    Back,
+    Mark,
+    MarkAll,
+    InvertMarks,
+    DeleteMarked,
+    Confirm,
+    Cancel,
+    Rescan,
+    AgeFilter,
+    MarkIdle,
+    SizeMode,
+    Undo,
     Tick,
 }
 
@@ -16,6 +29,19 @@ fn map_input_to_event(input_code: &KeyCode) -> Option<InputEventType> {
         KeyCode::Up => Some(InputEventType::Up),
         KeyCode::Down => Some(InputEventType::Down),
         KeyCode::Char(' ') => Some(InputEventType::Select),
+        KeyCode::Enter => Some(InputEventType::Open),
+        KeyCode::Backspace | KeyCode::Left | KeyCode::Esc => Some(InputEventType::Back),
+        KeyCode::Char('m') => Some(InputEventType::Mark),
+        KeyCode::Char('*') => Some(InputEventType::MarkAll),
+        KeyCode::Char('i') => Some(InputEventType::InvertMarks),
+        KeyCode::Char('d') => Some(InputEventType::DeleteMarked),
+        KeyCode::Char('y') => Some(InputEventType::Confirm),
+        KeyCode::Char('n') => Some(InputEventType::Cancel),
+        KeyCode::Char('r') => Some(InputEventType::Rescan),
+        KeyCode::Char('o') => Some(InputEventType::AgeFilter),
+        KeyCode::Char('O') => Some(InputEventType::MarkIdle),
+        KeyCode::Char('a') => Some(InputEventType::SizeMode),
+        KeyCode::Char('u') => Some(InputEventType::Undo),
         _ => None
     }
 }
@@ -45,9 +71,8 @@ impl InputEvent {
                         }
                     }
                 }
-                match tx_event.send(InputEventType::Tick) {
-                    Err(_) => break,
-                    _ => {}
+                if tx_event.send(InputEventType::Tick).is_err() {
+                    break;
                 }
             }
         });
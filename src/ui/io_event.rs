@@ -1,6 +1,25 @@
+use std::{path::PathBuf, time::SystemTime};
+
+use crate::file_helper::{TargetDir, DirStats};
+
+/// Search and size events start with the scan they belong to, so the results of a scan
+/// replaced by a rescan are dropped.
 pub enum IoEventType {
     Initialize,
+    Found(usize, TargetDir),
+    /// Target directory created after the search, found through the watcher.
+    Appeared(usize, TargetDir),
+    SearchDone(usize),
+    Created(usize, PathBuf),
+    Removed(usize, PathBuf),
     Deleted(usize),
     DeleteError(usize),
-    Loaded(usize, u128)
+    Loaded(usize, usize, DirStats),
+    /// Partial size of a directory still being measured, at most one queued per row.
+    SizeProgress(usize, usize, DirStats),
+    LastActivity(usize, usize, Option<SystemTime>),
+    /// Time to measure again a row that may still be filling up.
+    Settle(usize, usize),
+    /// Size of an entry of the drill-down level with the given id.
+    BrowseLoaded(usize, PathBuf, DirStats),
 }
\ No newline at end of file
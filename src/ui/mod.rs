@@ -1,8 +1,8 @@
-use std::{io, time::{Duration, Instant}, sync::Arc, fs::DirEntry};
+use std::{collections::HashSet, fmt, io, panic, process, thread, time::{Duration, Instant, SystemTime}, sync::{Arc, atomic::{AtomicBool, Ordering}}, path::{Path, PathBuf}};
 
 use crossterm::{terminal::{enable_raw_mode, EnterAlternateScreen, disable_raw_mode, LeaveAlternateScreen}, execute, event::{EnableMouseCapture, DisableMouseCapture}};
 use tokio::sync::Mutex;
-use tui::{widgets::TableState, Frame, backend::{Backend, CrosstermBackend}, layout::{Layout, Direction, Constraint, Rect}, Terminal};
+use tui::{widgets::{TableState, Clear}, Frame, backend::{Backend, CrosstermBackend}, layout::{Layout, Direction, Constraint, Rect}, Terminal};
 
 mod title;
 mod status;
@@ -12,14 +12,44 @@ mod io_event;
 mod table;
 mod table_placeholder;
 mod guideline;
+mod details;
+mod error_log;
+mod browser;
+mod confirm;
 
 use title::title;
 
-use crate::file_helper::{get_files_path, get_size, size};
+use crate::{file_helper::{get_files_path, get_size, get_last_activity, search_new_dir, size, move_to_trash, quarantine, restore, purge, write_manifest, purge_manifest, purge_leftovers, DirStats, CountedFiles, ErrorLog, ScanError, SizeCache, SearchOption, TargetDir, WorkerPool, CancelToken, Watcher, WatchEvent}, config::Config};
 
-use self::{status::status_block, version::version_block, input_event::{InputEvent, InputEventType}, io_event::IoEventType, table::table, table_placeholder::table_placeholder, guideline::guideline};
+use self::{status::{status_block, StatusInfo}, version::version_block, input_event::{InputEvent, InputEventType}, io_event::IoEventType, table::table, table_placeholder::table_placeholder, guideline::{guideline, guideline_height}, details::details_block, error_log::{error_log_block, ERROR_LOG_LINES}, browser::{BrowseLevel, browser_table}, confirm::{confirm_popup, confirm_lines, popup_area}};
 
 const CHANNEL_BUFFER: usize = 100;
+/// Delay before measuring again a directory created after the search, until its size holds.
+const SETTLE_DELAY: Duration = Duration::from_secs(2);
+/// Errors listed after the UI is closed, the rest are only counted.
+const SUMMARY_ERRORS: usize = 20;
+/// Rows the table needs to show one row: borders, header and its margin, and the row with
+/// its margin, or tui shows none once the cursor leaves the first row.
+const MIN_TABLE_HEIGHT: u16 = 6;
+const TITLE_HEIGHT: u16 = 7;
+const VERSION_HEIGHT: u16 = 2;
+/// Status and details blocks.
+const STATUS_HEIGHT: u16 = 6;
+
+/// What deleting a row does.
+#[derive(Clone, Copy)]
+enum DeleteMode {
+    /// Rename into quarantine right away, remove once the session ends.
+    Quarantine,
+    Task(TaskDelete),
+}
+
+/// Deletions left to a task, as they can take a while.
+#[derive(Clone, Copy)]
+enum TaskDelete {
+    Trash,
+    DryRun,
+}
 
 #[derive(PartialEq)]
 pub enum DirStatus {
@@ -27,105 +57,706 @@ pub enum DirStatus {
     Ready,
     Deleting,
     Deleted,
+    /// Moved aside, purged when the session ends unless restored first.
+    Quarantined,
     Error,
+    Removed,
+}
+
+/// Which of the sizes of a row is shown and added up.
+#[derive(Clone, Copy, PartialEq)]
+pub enum SizeMode {
+    Apparent,
+    Reclaimable,
+}
+
+impl SizeMode {
+    fn of(&self, size: &DirStats) -> u128 {
+        match self {
+            SizeMode::Apparent => size.apparent.into(),
+            SizeMode::Reclaimable => size.reclaimable.into(),
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            SizeMode::Apparent => "apparent",
+            SizeMode::Reclaimable => "reclaimable",
+        }
+    }
+
+    fn label(&self) -> &'static str {
+        match self {
+            SizeMode::Apparent => "Apparent size",
+            SizeMode::Reclaimable => "Reclaimable",
+        }
+    }
+
+    fn toggle(&self) -> SizeMode {
+        match self {
+            SizeMode::Apparent => SizeMode::Reclaimable,
+            SizeMode::Reclaimable => SizeMode::Apparent,
+        }
+    }
 }
 
 pub struct DirData {
+    root: String,
     path: String,
-    size: Option<u128>,
+    profile: String,
+    is_link: bool,
+    last_activity: Option<SystemTime>,
+    activity_loaded: bool,
+    size: Option<DirStats>,
+    /// The size comes from the cache and is being recalculated.
+    revalidating: bool,
+    /// Picked for the next "delete marked".
+    marked: bool,
+    /// Created after the search (e.g. by `npm install`), measured again until its size holds.
+    settling: bool,
+    /// Set while a partial size of the row waits in the I/O channel, so at most one is queued.
+    progress_queued: Arc<AtomicBool>,
     status: DirStatus,
 }
 
 impl DirData {
-    fn update_size(&mut self, size: u128) {
+    fn display_path(&self) -> &str {
+        match self.path.strip_prefix(&self.root) {
+            Some(path) if !path.is_empty() => path.trim_start_matches(std::path::MAIN_SEPARATOR),
+            _ => &self.path,
+        }
+    }
+
+    fn update_size(&mut self, size: DirStats) {
         self.size = Some(size);
+        self.revalidating = false;
         self.status = DirStatus::Ready;
     }
 
+    /// Some entries could not be read, so the size is lower than it should be.
+    fn is_partial(&self) -> bool {
+        self.size.map(|size| size.errors > 0).unwrap_or(false)
+    }
+
+    /// The size is a partial total of a directory still being measured.
+    fn is_provisional(&self) -> bool {
+        self.status == DirStatus::Loading && self.size.is_some()
+    }
+
+    fn is_sizing(&self) -> bool {
+        self.status == DirStatus::Loading || (self.revalidating && self.status == DirStatus::Ready)
+    }
+
+    /// Only rows with a fresh size are deleted: a cached size may be stale, and the walk
+    /// checking it would run into the deleted directory.
+    fn can_delete(&self) -> bool {
+        self.status == DirStatus::Ready && !self.revalidating
+    }
+
     fn deleting(&mut self) {
         self.status = DirStatus::Deleting;
+        self.marked = false;
+    }
+
+    /// Rows that are gone or on their way out cannot be marked.
+    fn can_mark(&self) -> bool {
+        !self.is_gone() && self.status != DirStatus::Deleting
+    }
+
+    /// The directory is no longer at its path.
+    fn is_gone(&self) -> bool {
+        matches!(self.status, DirStatus::Deleted | DirStatus::Quarantined | DirStatus::Removed)
     }
 
     fn deleted(&mut self) {
         self.status = DirStatus::Deleted;
     }
 
+    fn quarantined(&mut self) {
+        self.status = DirStatus::Quarantined;
+    }
+
     fn error(&mut self) {
         self.status = DirStatus::Error;
     }
+
+    fn removed(&mut self) {
+        self.status = DirStatus::Removed;
+        self.marked = false;
+    }
+
+    /// Whether the project has been idle for at least `threshold`; projects without any file
+    /// count as idle, rows whose last activity is still being computed do not.
+    fn is_idle_for(&self, threshold: Duration) -> bool {
+        if !self.activity_loaded {
+            return false;
+        }
+
+        match self.last_activity {
+            Some(time) => SystemTime::now().duration_since(time).map(|idle| idle >= threshold).unwrap_or(false),
+            None => true,
+        }
+    }
+
+    fn is_inside(&self, path: &Path) -> bool {
+        Path::new(&self.path).starts_with(path)
+    }
 }
 
 struct App {
     data: Option<Vec<DirData>>,
+    searching: bool,
+    /// Index into `data` of the row under the cursor. Rows come, go and get filtered while the
+    /// cursor stays, so `state` only mirrors its position among the shown rows when drawing.
+    selected: Option<usize>,
     state: TableState,
-    total_size: Option<u128>,
+    age_threshold: Duration,
+    age_filter: bool,
+    /// Mark rows as soon as their last activity shows them idle.
+    mark_idle: bool,
+    size_mode: SizeMode,
+    ask_confirmation: bool,
+    /// Move deleted directories to the trash instead of removing them.
+    trash: bool,
+    /// Go through deletions without touching the filesystem.
+    dry_run: bool,
+    /// Rows waiting for the deletion to be confirmed.
+    pending_delete: Option<Vec<usize>>,
     time_init: Option<Duration>,
     free_space: u128,
     io_tx: tokio::sync::mpsc::Sender<IoEventType>,
+    pool: WorkerPool,
+    search_option: Arc<SearchOption>,
+    watch: bool,
+    watcher: Option<Watcher>,
+    scan: Scan,
+    /// Errors of the scans replaced by a rescan, still listed when the UI is closed.
+    past_errors: Vec<ScanError>,
+    /// Directories opened in the drill-down view, the innermost last.
+    browser: Vec<BrowseLevel>,
+    browse_id: usize,
+    cache: Option<SizeCache>,
+    started_at: Instant,
+    /// Deleted directories waiting to be purged, the latest last.
+    quarantine: Vec<QuarantinedDir>,
+    /// File listing `quarantine`, purged by the next start if this process dies first.
+    quarantine_manifest: Option<PathBuf>,
+}
+
+/// A deleted directory moved aside until the session ends.
+struct QuarantinedDir {
+    /// Scan and row it was deleted from, the row is gone after a rescan.
+    scan: usize,
+    index: usize,
+    path: PathBuf,
+    moved: PathBuf,
+    reclaimable: u64,
 }
 
-fn cal_size(io_tx: tokio::sync::mpsc::Sender<IoEventType>, index: usize, path: DirEntry) {
+/// State of one search and the size calculations it started, replaced on rescan.
+#[derive(Clone, Default)]
+struct Scan {
+    id: usize,
+    cancel: CancelToken,
+    counted: CountedFiles,
+    errors: ErrorLog,
+}
+
+impl Scan {
+    /// Cancel this scan and start the next one.
+    fn next(&self) -> Scan {
+        self.cancel.cancel();
+        Scan { id: self.id + 1, ..Scan::default() }
+    }
+}
+
+fn cal_size(io_tx: tokio::sync::mpsc::Sender<IoEventType>, pool: WorkerPool, option: Arc<SearchOption>, scan: Scan, index: usize, path: PathBuf, queued: Arc<AtomicBool>) {
+    tokio::spawn(async move {
+        // A partial size is only sent once the previous one was applied, and dropped rather
+        // than waited for when the channel is full: the next one comes soon enough.
+        let progress = |size| {
+            if queued.swap(true, Ordering::AcqRel) {
+                return;
+            }
+            if io_tx.try_send(IoEventType::SizeProgress(scan.id, index, size)).is_err() {
+                queued.store(false, Ordering::Release);
+            }
+        };
+        let size = get_size(pool, path, option, scan.counted.clone(), scan.errors.clone(), scan.cancel.clone(), progress).await;
+        if scan.cancel.is_cancelled() {
+            return;
+        }
+        let _ = io_tx.send(IoEventType::Loaded(scan.id, index, size)).await;
+    });
+}
+
+fn cal_last_activity(io_tx: tokio::sync::mpsc::Sender<IoEventType>, pool: WorkerPool, option: Arc<SearchOption>, scan: Scan, index: usize, root: Arc<Path>, path: PathBuf) {
     tokio::spawn(async move {
-        let size = get_size(path.path().as_path()).await;
-        if let Err(_) = io_tx.send(IoEventType::Loaded(index, size.into())).await {
+        let last_activity = get_last_activity(pool, root, path, option, scan.cancel.clone()).await;
+        if scan.cancel.is_cancelled() {
             return;
         }
+        let _ = io_tx.send(IoEventType::LastActivity(scan.id, index, last_activity)).await;
     });
 }
 
-fn delete_file(io_tx: tokio::sync::mpsc::Sender<IoEventType>, index: usize, path: String) {
+/// Forward found directories to the I/O loop as `event`s of `scan`.
+async fn forward_found(io_tx: &tokio::sync::mpsc::Sender<IoEventType>, scan: usize, event: fn(usize, TargetDir) -> IoEventType, mut rx: tokio::sync::mpsc::Receiver<TargetDir>) -> bool {
+    while let Some(target) = rx.recv().await {
+        if io_tx.send(event(scan, target)).await.is_err() {
+            return false;
+        }
+    }
+    true
+}
+
+/// Measure every entry of a level of the drill-down view.
+fn cal_browse_size(io_tx: tokio::sync::mpsc::Sender<IoEventType>, pool: WorkerPool, option: Arc<SearchOption>, level: &BrowseLevel) {
+    for entry in &level.entries {
+        let (io_tx, pool, option, cancel, id, path) = (io_tx.clone(), pool.clone(), option.clone(), level.cancel.clone(), level.id, entry.path.clone());
+        tokio::spawn(async move {
+            let size = get_size(pool, path.clone(), option, CountedFiles::default(), ErrorLog::default(), cancel.clone(), |_| {}).await;
+            if cancel.is_cancelled() {
+                return;
+            }
+            let _ = io_tx.send(IoEventType::BrowseLoaded(id, path, size)).await;
+        });
+    }
+}
+
+/// Run the search in the background, turning every found directory into an I/O event.
+fn search(io_tx: tokio::sync::mpsc::Sender<IoEventType>, pool: WorkerPool, scan: Scan, search_option: Arc<SearchOption>, watcher: Option<Watcher>) {
     tokio::spawn(async move {
-        let result = remove_dir_all::remove_dir_all(path);
+        let (tx, rx) = tokio::sync::mpsc::channel::<TargetDir>(CHANNEL_BUFFER);
+        tokio::spawn(get_files_path(pool, search_option, scan.cancel.clone(), watcher, scan.errors.clone(), tx));
+
+        if forward_found(&io_tx, scan.id, IoEventType::Found, rx).await && !scan.cancel.is_cancelled() {
+            let _ = io_tx.send(IoEventType::SearchDone(scan.id)).await;
+        }
+    });
+}
+
+/// Search a directory reported by the watcher, adding any target directory to the current scan.
+fn search_created(io_tx: tokio::sync::mpsc::Sender<IoEventType>, pool: WorkerPool, scan: Scan, search_option: Arc<SearchOption>, watcher: Option<Watcher>, path: PathBuf) {
+    tokio::spawn(async move {
+        let (tx, rx) = tokio::sync::mpsc::channel::<TargetDir>(CHANNEL_BUFFER);
+        tokio::spawn(search_new_dir(pool, search_option, scan.cancel.clone(), watcher, scan.errors.clone(), path, tx));
+        forward_found(&io_tx, scan.id, IoEventType::Appeared, rx).await;
+    });
+}
+
+fn settle_later(io_tx: tokio::sync::mpsc::Sender<IoEventType>, scan: usize, index: usize) {
+    tokio::spawn(async move {
+        tokio::time::sleep(SETTLE_DELAY).await;
+        let _ = io_tx.send(IoEventType::Settle(scan, index)).await;
+    });
+}
+
+fn watch(io_tx: tokio::sync::mpsc::Sender<IoEventType>, scan: usize, mut rx: tokio::sync::mpsc::Receiver<WatchEvent>) {
+    tokio::spawn(async move {
+        while let Some(event) = rx.recv().await {
+            let event = match event {
+                WatchEvent::Created(path) => IoEventType::Created(scan, path),
+                WatchEvent::Removed(path) => IoEventType::Removed(scan, path),
+            };
+            if io_tx.send(event).await.is_err() {
+                return;
+            }
+        }
+    });
+}
+
+fn delete_file(io_tx: tokio::sync::mpsc::Sender<IoEventType>, errors: ErrorLog, mode: TaskDelete, index: usize, path: String) {
+    tokio::spawn(async move {
+        let result = match mode {
+            TaskDelete::Trash => move_to_trash(Path::new(&path)),
+            TaskDelete::DryRun => Ok(()),
+        };
         let event = match result {
-            Err(_) => IoEventType::DeleteError(index),
+            Err(error) => {
+                errors.push(Path::new(&path), &error);
+                IoEventType::DeleteError(index)
+            },
             Ok(_) => IoEventType::Deleted(index),
         };
-        if let Err(_) = io_tx.send(event).await {
-            return;
-        }
+        let _ = io_tx.send(event).await;
     });
 }
 
 impl App {
-    fn new(io_tx: tokio::sync::mpsc::Sender<IoEventType>) -> App {
-        App { data: None, state: TableState::default(), total_size: None, time_init: None, free_space: 0, io_tx }
+    fn new(io_tx: tokio::sync::mpsc::Sender<IoEventType>, pool: WorkerPool, search_option: SearchOption, config: &Config) -> App {
+        App {
+            data: None,
+            searching: true,
+            selected: None,
+            state: TableState::default(),
+            age_threshold: config.age_threshold(),
+            age_filter: config.older_than.is_some(),
+            mark_idle: config.mark_idle,
+            size_mode: SizeMode::Reclaimable,
+            ask_confirmation: !config.no_confirm,
+            trash: config.trash,
+            dry_run: config.dry_run,
+            pending_delete: None,
+            time_init: None,
+            free_space: 0,
+            io_tx,
+            pool,
+            search_option: Arc::new(search_option),
+            watch: config.watch,
+            watcher: None,
+            scan: Scan::default(),
+            past_errors: vec![],
+            browser: vec![],
+            browse_id: 0,
+            cache: config.size_cache(),
+            started_at: Instant::now(),
+            quarantine: vec![],
+            quarantine_manifest: config.quarantine_manifest(),
+        }
+    }
+
+    /// Cancel the current scan, if any, and search all roots again from an empty list.
+    fn start_search(&mut self) {
+        self.past_errors.extend(self.scan.errors.all());
+        self.scan = self.scan.next();
+        self.close_browser();
+
+        self.data = None;
+        self.searching = true;
+        self.selected = None;
+        self.state = TableState::default();
+        self.time_init = None;
+        self.started_at = Instant::now();
+
+        self.watcher = None;
+        if self.watch {
+            if let Some((watcher, rx)) = Watcher::new(self.scan.cancel.clone()) {
+                watch(self.io_tx.clone(), self.scan.id, rx);
+                self.watcher = Some(watcher);
+            }
+        }
+
+        search(self.io_tx.clone(), self.pool.clone(), self.scan.clone(), self.search_option.clone(), self.watcher.clone());
     }
 
-    fn update_data(&mut self, paths: Vec<DirEntry>) {
-        if let None = self.data {
-            let data: Vec<DirData> = paths.into_iter().enumerate().map(|(index, path)| {
-                let file_path = path.path().display().to_string();
-                let result = DirData{path: file_path, size: None, status: DirStatus::Loading};
-                let io_tx = self.io_tx.clone();
-                cal_size(io_tx, index, path);
-                return result;
-            }).collect();
-            self.data = Some(data);
+    fn rescan(&mut self) {
+        let is_deleting = match &self.data {
+            Some(data) => data.iter().any(|item| item.status == DirStatus::Deleting),
+            None => false,
+        };
+
+        // Deletions report back by row index, so the rows must stay put until they finish.
+        if !is_deleting {
+            self.start_search();
+        }
+    }
+
+    fn cal_size(&mut self, index: usize, path: PathBuf) {
+        let queued = Arc::new(AtomicBool::new(false));
+        let root: Arc<Path> = match &mut self.data {
+            Some(data) => {
+                data[index].progress_queued = queued.clone();
+                Arc::from(Path::new(&data[index].root))
+            },
+            None => return,
+        };
+
+        cal_size(self.io_tx.clone(), self.pool.clone(), self.search_option.clone(), self.scan.clone(), index, path.clone(), queued);
+        cal_last_activity(self.io_tx.clone(), self.pool.clone(), self.search_option.clone(), self.scan.clone(), index, root, path);
+    }
+
+    /// Add a found directory; `settling` when it was just created and may still fill up.
+    fn add_data(&mut self, target: TargetDir, settling: bool) {
+        let data = self.data.get_or_insert_with(Vec::new);
+        let file_path = target.entry.path().display().to_string();
+
+        // The watcher can report a directory the search already listed, or one that came back.
+        if let Some(index) = data.iter().position(|item| item.path == file_path) {
+            if data[index].status == DirStatus::Removed {
+                data[index].size = None;
+                data[index].revalidating = false;
+                data[index].activity_loaded = false;
+                data[index].settling = settling;
+                data[index].status = DirStatus::Loading;
+                self.time_init = None;
+                self.cal_size(index, target.entry.path());
+            }
+            return;
+        }
+
+        let index = data.len();
+        let root = target.root.display().to_string();
+        let cached = self.cache.as_ref().and_then(|cache| cache.get(&target.entry.path()));
+        let status = match cached {
+            Some(_) => DirStatus::Ready,
+            None => DirStatus::Loading,
+        };
+        data.push(DirData{root, path: file_path, profile: target.profile, is_link: target.is_link, last_activity: None, activity_loaded: false, size: cached, revalidating: cached.is_some(), marked: false, settling, progress_queued: Arc::default(), status});
+
+        self.time_init = None;
+        self.cal_size(index, target.entry.path());
+    }
+
+    fn search_created(&self, path: PathBuf) {
+        search_created(self.io_tx.clone(), self.pool.clone(), self.scan.clone(), self.search_option.clone(), self.watcher.clone(), path);
+    }
+
+    /// Mark the rows at or below a directory removed by someone else.
+    fn removed(&mut self, path: PathBuf) {
+        if let Some(data) = &mut self.data {
+            for item in data.iter_mut().filter(|item| item.is_inside(&path)) {
+                if item.status == DirStatus::Deleting || item.status == DirStatus::Deleted || item.status == DirStatus::Quarantined {
+                    continue;
+                }
+
+                item.removed();
+            }
+        }
+        self.check_loaded();
+    }
+
+    fn search_done(&mut self) {
+        self.searching = false;
+        self.data.get_or_insert_with(Vec::new);
+        self.check_loaded();
+    }
+
+    fn check_loaded(&mut self) {
+        if let Some(data) = &self.data {
+            if !self.searching && self.time_init.is_none() && data.iter().all(|item| !item.is_sizing()) {
+                self.time_init = Some(self.started_at.elapsed());
+            }
         }
     }
 
     fn delete_file(&mut self) {
-        let index = match self.state.selected() {
+        if let Some(index) = self.selected_index() {
+            self.request_delete(vec![index]);
+        }
+    }
+
+    /// Delete the ready rows among `indices`, once confirmed unless confirmation is turned off.
+    fn request_delete(&mut self, indices: Vec<usize>) {
+        let indices: Vec<usize> = match &self.data {
+            Some(data) => indices.into_iter().filter(|index| data[*index].can_delete()).collect(),
+            None => return,
+        };
+
+        if indices.is_empty() {
+            return;
+        }
+
+        match self.ask_confirmation {
+            true => self.pending_delete = Some(indices),
+            false => indices.into_iter().for_each(|index| self.delete_row(index)),
+        }
+    }
+
+    fn confirm_delete(&mut self) {
+        if let Some(indices) = self.pending_delete.take() {
+            for index in indices {
+                self.delete_row(index);
+            }
+        }
+    }
+
+    fn cancel_delete(&mut self) {
+        self.pending_delete = None;
+    }
+
+    /// Paths and combined size of the rows waiting for confirmation.
+    fn pending_summary(&self) -> Option<(Vec<String>, u128)> {
+        let (indices, data) = match (&self.pending_delete, &self.data) {
+            (Some(indices), Some(data)) => (indices, data),
+            _ => return None,
+        };
+
+        let paths = indices.iter().map(|index| data[*index].path.clone()).collect();
+        let size = indices.iter().filter_map(|index| data[*index].size.as_ref()).map(|size| self.size_mode.of(size)).sum();
+        Some((paths, size))
+    }
+
+    fn delete_mode(&self) -> DeleteMode {
+        match (self.dry_run, self.trash) {
+            (true, _) => DeleteMode::Task(TaskDelete::DryRun),
+            (false, true) => DeleteMode::Task(TaskDelete::Trash),
+            (false, false) => DeleteMode::Quarantine,
+        }
+    }
+
+    fn delete_row(&mut self, index: usize) {
+        let mode = self.delete_mode();
+        let path = match &mut self.data {
+            Some(data) if data[index].can_delete() => {
+                data[index].deleting();
+                data[index].path.clone()
+            },
+            _ => return,
+        };
+
+        match mode {
+            // A rename is instant, so it is done here rather than by a task that could
+            // still be running when the session ends and the quarantine is purged.
+            DeleteMode::Quarantine => self.quarantine_row(index),
+            DeleteMode::Task(mode) => delete_file(self.io_tx.clone(), self.scan.errors.clone(), mode, index, path),
+        }
+    }
+
+    fn quarantine_row(&mut self, index: usize) {
+        let path = match &self.data {
+            Some(data) => PathBuf::from(&data[index].path),
             None => return,
+        };
+
+        let moved = match quarantine(&path) {
+            Ok(moved) => moved,
+            Err(error) => {
+                self.scan.errors.push(&path, &error);
+                self.deleted_error(index);
+                return;
+            }
+        };
+
+        if let Some(data) = &mut self.data {
+            data[index].quarantined();
+            if let Some(cache) = &mut self.cache {
+                cache.remove(&path);
+            }
+            let reclaimable = data[index].size.map(|size| size.reclaimable).unwrap_or(0);
+            self.free_space += u128::from(reclaimable);
+            self.quarantine.push(QuarantinedDir { scan: self.scan.id, index, path, moved, reclaimable });
+        }
+        self.save_quarantine_manifest();
+    }
+
+    fn save_quarantine_manifest(&mut self) {
+        if let Some(file) = &self.quarantine_manifest {
+            if let Err(error) = write_manifest(file, self.quarantine.iter().map(|dir| dir.moved.as_path())) {
+                self.scan.errors.push(file, &error);
+            }
+        }
+    }
+
+    /// Put back the quarantined row under the cursor, or else the last one quarantined.
+    fn undo_delete(&mut self) {
+        let (scan, selected) = (self.scan.id, self.selected_index());
+        let position = match self.quarantine.iter().rposition(|dir| dir.scan == scan && Some(dir.index) == selected) {
+            Some(position) => position,
+            None => match self.quarantine.len() {
+                0 => return,
+                len => len - 1,
+            },
+        };
+
+        let dir = &self.quarantine[position];
+        if let Err(error) = restore(&dir.moved, &dir.path) {
+            self.scan.errors.push(&dir.path, &error);
+            return;
+        }
+
+        let dir = self.quarantine.remove(position);
+        self.free_space = self.free_space.saturating_sub(u128::from(dir.reclaimable));
+        self.save_quarantine_manifest();
+
+        // After a rescan the row is gone, the directory shows up again on the next one.
+        if dir.scan != scan {
+            return;
+        }
+
+        // It may have changed while away, so it is measured again like a cached row.
+        if let Some(data) = &mut self.data {
+            data[dir.index].status = DirStatus::Ready;
+            data[dir.index].revalidating = true;
+        }
+        self.cal_size(dir.index, dir.path);
+    }
+
+    /// Remove the quarantined directories for good, once the session is over. Directories
+    /// that stay behind no longer count as freed.
+    fn purge_quarantine(&mut self) {
+        for dir in std::mem::take(&mut self.quarantine) {
+            match purge(&dir.moved) {
+                Ok(_) => {},
+                Err(error) => {
+                    self.scan.errors.push(&dir.path, &error);
+                    self.free_space = self.free_space.saturating_sub(u128::from(dir.reclaimable));
+                    self.quarantine.push(dir);
+                },
+            }
+        }
+        // Whatever could not be purged is tried again by the next start.
+        self.save_quarantine_manifest();
+    }
+
+    /// Indexes into `data` of the shown rows that are marked.
+    fn marked_indices(&self) -> Vec<usize> {
+        match &self.data {
+            Some(data) => self.visible_indices().into_iter().filter(|index| data[*index].marked).collect(),
+            None => vec![],
+        }
+    }
+
+    /// Delete every shown marked row that is ready; rows still being measured stay marked.
+    fn delete_marked(&mut self) {
+        let indices = self.marked_indices();
+        self.request_delete(indices);
+    }
+
+    /// Mark or unmark the row under the cursor and move to the next one.
+    fn toggle_mark(&mut self) {
+        let index = match self.selected_index() {
             Some(index) => index,
+            None => return,
         };
 
         if let Some(data) = &mut self.data {
-            if data[index].status != DirStatus::Ready {
-                return;
+            if data[index].can_mark() {
+                data[index].marked = !data[index].marked;
             }
+        }
+        self.next();
+    }
 
-            data[index].deleting();
-            let io_tx = self.io_tx.clone();
-            delete_file(io_tx, index, data[index].path.clone());
+    fn mark_all(&mut self) {
+        let indices = self.visible_indices();
+        if let Some(data) = &mut self.data {
+            for index in indices {
+                data[index].marked = data[index].can_mark();
+            }
+        }
+    }
+
+    fn invert_marks(&mut self) {
+        let indices = self.visible_indices();
+        if let Some(data) = &mut self.data {
+            for index in indices {
+                data[index].marked = !data[index].marked && data[index].can_mark();
+            }
+        }
+    }
+
+    /// Mark every shown row idle for at least the age threshold.
+    fn mark_idle(&mut self) {
+        let indices = self.visible_indices();
+        let threshold = self.age_threshold;
+        if let Some(data) = &mut self.data {
+            for index in indices {
+                if data[index].can_mark() && data[index].is_idle_for(threshold) {
+                    data[index].marked = true;
+                }
+            }
         }
     }
 
     fn deleted_file(&mut self, index: usize) {
         if let Some(data) = &mut self.data {
             data[index].deleted();
+            if let (Some(cache), false) = (&mut self.cache, self.dry_run) {
+                cache.remove(Path::new(&data[index].path));
+            }
             if let Some(size) = data[index].size {
-                self.free_space += size;
+                self.free_space += u128::from(size.reclaimable);
             }
         }
     }
@@ -136,62 +767,269 @@ impl App {
         }
     }
 
-    fn update_size(&mut self, index: usize, size: u128, instant: Instant) {
+    fn update_size(&mut self, index: usize, size: DirStats) {
         if let Some(data) = &mut self.data {
+            if !data[index].is_sizing() {
+                return;
+            }
+
+            // A re-measure keeps the previous size on screen until it is done.
+            let unchanged = data[index].revalidating && data[index].size.map(|old| (old.apparent, old.files)) == Some((size.apparent, size.files));
             data[index].update_size(size);
-            let mut added_size = size;
-            if let Some(current_size) = self.total_size {
-                added_size += current_size;
+            if data[index].settling {
+                match unchanged {
+                    true => data[index].settling = false,
+                    false => settle_later(self.io_tx.clone(), self.scan.id, index),
+                }
             }
-            self.total_size = Some(added_size);
-            match data.iter().find(|item| item.size == None) {
-                None => {
-                    self.time_init = Some(instant.elapsed());
-                },
-                Some(_) => {}
+            if let Some(cache) = &mut self.cache {
+                match size.errors {
+                    0 => cache.insert(Path::new(&data[index].path), size),
+                    _ => cache.remove(Path::new(&data[index].path)),
+                }
             }
         }
+        self.check_loaded();
     }
 
-    pub fn next(&mut self) {
-        let i = match self.state.selected() {
-            Some(i) => {
-                if let Some(data) = &self.data {
-                    if i >= data.len() - 1 {
-                        0
-                    } else {
-                        i + 1
-                    }
-                } else {
-                    0
-                }
+    /// Measure again a row that may still be filling up, showing its last size meanwhile.
+    fn settle(&mut self, index: usize) {
+        let path = match &mut self.data {
+            Some(data) if data[index].settling && data[index].status == DirStatus::Ready => {
+                data[index].revalidating = true;
+                PathBuf::from(&data[index].path)
+            },
+            _ => return,
+        };
+
+        self.cal_size(index, path);
+    }
+
+    fn size_progress(&mut self, index: usize, size: DirStats) {
+        if let Some(data) = &mut self.data {
+            data[index].progress_queued.store(false, Ordering::Release);
+            // A cached size stays on screen until the new one is complete.
+            if data[index].status == DirStatus::Loading {
+                data[index].size = Some(size);
+            }
+        }
+    }
+
+    fn update_last_activity(&mut self, index: usize, last_activity: Option<SystemTime>) {
+        if let Some(data) = &mut self.data {
+            // Only the first time, a row the user unmarked stays unmarked when measured again.
+            let first = !data[index].activity_loaded;
+            data[index].last_activity = last_activity;
+            data[index].activity_loaded = true;
+            if self.mark_idle && first && data[index].can_mark() && data[index].is_idle_for(self.age_threshold) {
+                data[index].marked = true;
             }
+        }
+    }
+
+    fn is_visible(&self, item: &DirData) -> bool {
+        !self.age_filter || item.is_idle_for(self.age_threshold)
+    }
+
+    /// Indexes into `data` of the rows shown in the table.
+    fn visible_indices(&self) -> Vec<usize> {
+        match &self.data {
+            Some(data) => (0..data.len()).filter(|index| self.is_visible(&data[*index])).collect(),
+            None => vec![],
+        }
+    }
+
+    fn visible_data(&self) -> Vec<&DirData> {
+        match &self.data {
+            Some(data) => data.iter().filter(|item| self.is_visible(item)).collect(),
+            None => vec![],
+        }
+    }
+
+    /// Index into `data` of the row under the cursor.
+    fn selected_index(&self) -> Option<usize> {
+        self.selected.filter(|index| self.visible_indices().contains(index))
+    }
+
+    /// Position of the selected row among the shown rows.
+    fn selected_position(&self, visible: &[usize]) -> Option<usize> {
+        visible.iter().position(|index| Some(*index) == self.selected)
+    }
+
+    fn status_info(&self) -> StatusInfo {
+        StatusInfo {
+            total_size: self.total_size(),
+            provisional: self.is_total_provisional(),
+            size_mode: self.size_mode,
+            time_init: self.time_init,
+            free_space: self.free_space,
+            trash: self.trash && !self.dry_run,
+            marked: self.marked_summary(),
+            searching: self.searching,
+            watching: self.watcher.is_some(),
+            age_filter: match self.age_filter {
+                true => Some(self.age_threshold),
+                false => None,
+            },
+        }
+    }
+
+    /// Whether a shown row is still being measured, so the total is going to change.
+    fn is_total_provisional(&self) -> bool {
+        self.visible_data().iter().any(|item| item.is_sizing())
+    }
+
+    /// Number and size of the shown marked rows.
+    fn marked_summary(&self) -> (usize, u128) {
+        let indices = self.marked_indices();
+        let size = match &self.data {
+            Some(data) => indices.iter().filter_map(|index| data[*index].size.as_ref()).map(|size| self.size_mode.of(size)).sum(),
             None => 0,
         };
-        self.state.select(Some(i));
+
+        (indices.len(), size)
+    }
+
+    /// Size of the shown rows, leaving out rows removed outside of the app.
+    fn total_size(&self) -> Option<u128> {
+        let sizes: Vec<u128> = self.visible_data()
+            .iter()
+            .filter(|item| item.status != DirStatus::Removed)
+            .filter_map(|item| item.size.as_ref().map(|size| self.size_mode.of(size)))
+            .collect();
+
+        match sizes.is_empty() {
+            true => None,
+            false => Some(sizes.into_iter().sum()),
+        }
+    }
+
+    /// A dry run leaves the cache as it was, like everything else on disk.
+    fn save_cache(&self) {
+        if self.dry_run {
+            return;
+        }
+
+        if let Some(cache) = &self.cache {
+            let _ = cache.save();
+        }
+    }
+
+    fn toggle_size_mode(&mut self) {
+        self.size_mode = self.size_mode.toggle();
+        for level in &mut self.browser {
+            level.sort(self.size_mode);
+        }
+    }
+
+    /// Open the selected row, or the selected directory of the drill-down view, one level deeper.
+    fn open(&mut self) {
+        let path = match self.browser.last() {
+            Some(level) => match level.selected() {
+                Some(entry) if entry.is_dir => entry.path.clone(),
+                _ => return,
+            },
+            None => match self.selected_index().and_then(|index| self.data.as_ref().map(|data| &data[index])) {
+                Some(item) if !item.is_gone() => PathBuf::from(&item.path),
+                _ => return,
+            },
+        };
+
+        self.browse_id += 1;
+        let level = BrowseLevel::read(self.browse_id, path, &self.scan.errors);
+        cal_browse_size(self.io_tx.clone(), self.pool.clone(), self.search_option.clone(), &level);
+        self.browser.push(level);
+    }
+
+    /// Leave the innermost level of the drill-down view.
+    fn back(&mut self) {
+        if let Some(level) = self.browser.pop() {
+            level.cancel.cancel();
+        }
+    }
+
+    fn close_browser(&mut self) {
+        while !self.browser.is_empty() {
+            self.back();
+        }
+    }
+
+    fn browse_size(&mut self, id: usize, path: PathBuf, size: DirStats) {
+        let size_mode = self.size_mode;
+        if let Some(level) = self.browser.iter_mut().find(|level| level.id == id) {
+            level.update_size(&path, size, size_mode);
+        }
+    }
+
+    fn up(&mut self) {
+        match self.browser.last_mut() {
+            Some(level) => level.previous(),
+            None => self.previous(),
+        }
+    }
+
+    fn down(&mut self) {
+        match self.browser.last_mut() {
+            Some(level) => level.next(),
+            None => self.next(),
+        }
+    }
+
+    fn select(&mut self) {
+        // Nothing is deleted from inside the drill-down view.
+        if self.browser.is_empty() {
+            self.delete_file();
+        }
+    }
+
+    fn toggle_age_filter(&mut self) {
+        self.age_filter = !self.age_filter;
+    }
+
+    /// Point the table at the selected row, moving the cursor to the next shown row when the
+    /// selected one got hidden.
+    fn sync_selection(&mut self) {
+        let visible = self.visible_indices();
+        let position = match self.selected {
+            Some(selected) => self.selected_position(&visible)
+                .or_else(|| visible.iter().position(|index| *index > selected))
+                .or_else(|| visible.len().checked_sub(1)),
+            None => None,
+        };
+
+        self.selected = position.map(|position| visible[position]);
+        self.state.select(position);
+    }
+
+    pub fn next(&mut self) {
+        let visible = self.visible_indices();
+        if visible.is_empty() {
+            return;
+        }
+
+        let i = match self.selected_position(&visible) {
+            Some(i) if i + 1 < visible.len() => i + 1,
+            _ => 0,
+        };
+        self.selected = Some(visible[i]);
     }
 
     pub fn previous(&mut self) {
-        let i = match self.state.selected() {
-            Some(i) => {
-                if let Some(data) = &self.data {
-                    if i == 0 {
-                        data.len() - 1
-                    } else {
-                        i - 1
-                    }    
-                } else {
-                    0
-                }
-            }
-            None => 0,
+        let visible = self.visible_indices();
+        if visible.is_empty() {
+            return;
+        }
+
+        let i = match self.selected_position(&visible) {
+            Some(i) if i > 0 => i - 1,
+            _ => visible.len() - 1,
         };
-        self.state.select(Some(i));
+        self.selected = Some(visible[i]);
     }
 }
 
 struct IoAsyncHandler {
-    app: Arc<Mutex<App>>
+    app: Arc<Mutex<App>>,
 }
 
 impl IoAsyncHandler {
@@ -199,16 +1037,42 @@ impl IoAsyncHandler {
         IoAsyncHandler { app }
     }
 
-    pub async fn handle_io_event(&mut self, io_event: IoEventType, instant: Instant) {
+    pub async fn handle_io_event(&mut self, io_event: IoEventType) {
         let mut app = self.app.lock().await;
 
         match io_event {
             IoEventType::Initialize => {
-                let paths = self.initialize().await;
-                app.update_data(paths);
+                app.start_search();
+            },
+            IoEventType::Found(scan, target) if scan == app.scan.id => {
+                app.add_data(target, false);
+            },
+            IoEventType::Appeared(scan, target) if scan == app.scan.id => {
+                app.add_data(target, true);
+            },
+            IoEventType::Settle(scan, index) if scan == app.scan.id => {
+                app.settle(index);
+            },
+            IoEventType::SearchDone(scan) if scan == app.scan.id => {
+                app.search_done();
+            },
+            IoEventType::Created(scan, path) if scan == app.scan.id => {
+                app.search_created(path);
+            },
+            IoEventType::Removed(scan, path) if scan == app.scan.id => {
+                app.removed(path);
+            },
+            IoEventType::Loaded(scan, index, size) if scan == app.scan.id => {
+                app.update_size(index, size);
+            },
+            IoEventType::SizeProgress(scan, index, size) if scan == app.scan.id => {
+                app.size_progress(index, size);
             },
-            IoEventType::Loaded(index, size) => {
-                app.update_size(index, size, instant);
+            IoEventType::BrowseLoaded(id, path, size) => {
+                app.browse_size(id, path, size);
+            },
+            IoEventType::LastActivity(scan, index, last_activity) if scan == app.scan.id => {
+                app.update_last_activity(index, last_activity);
             },
             IoEventType::Deleted(index) => {
                 app.deleted_file(index);
@@ -216,15 +1080,88 @@ impl IoAsyncHandler {
             IoEventType::DeleteError(index) => {
                 app.deleted_error(index);
             }
+            _ => {}
         };
     }
-    
-    async fn initialize(&self) -> Vec<DirEntry> {
-        get_files_path().await
+}
+
+/// What is printed once the UI is closed.
+pub struct ExitSummary {
+    pub free_space: u128,
+    pub trash: bool,
+    pub dry_run: bool,
+    pub errors: Vec<ScanError>,
+}
+
+impl fmt::Display for ExitSummary {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match (self.dry_run, self.trash) {
+            (true, _) => write!(f, "Free: {} (dry run, nothing was deleted)", size(self.free_space))?,
+            (false, true) => write!(f, "Moved to trash: {}", size(self.free_space))?,
+            (false, false) => write!(f, "Free: {}", size(self.free_space))?,
+        }
+
+        if !self.errors.is_empty() {
+            write!(f, "\n{} paths could not be read or deleted, sizes may be incomplete:", self.errors.len())?;
+        }
+        for error in self.errors.iter().take(SUMMARY_ERRORS) {
+            write!(f, "\n  {}: {}", error.kind, error.path.display())?;
+        }
+        if self.errors.len() > SUMMARY_ERRORS {
+            write!(f, "\n  .. and {} more", self.errors.len() - SUMMARY_ERRORS)?;
+        }
+
+        Ok(())
     }
 }
 
-pub async fn start_ui() -> Result<String, io::Error> {
+/// Purge the quarantine when the UI panics, the shutdown never runs then.
+fn purge_on_panic(manifest: PathBuf) {
+    let previous = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        // Pool jobs and I/O tasks panic on other threads, which the session survives.
+        if thread::current().name() == Some("main") {
+            purge_manifest(&manifest);
+        }
+        previous(info);
+    }));
+}
+
+/// Purge the quarantine and leave when the terminal is closed or the process is told to stop.
+#[cfg(unix)]
+fn purge_on_signal(manifest: PathBuf) {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    let (mut hangup, mut terminate) = match (signal(SignalKind::hangup()), signal(SignalKind::terminate())) {
+        (Ok(hangup), Ok(terminate)) => (hangup, terminate),
+        _ => return,
+    };
+
+    tokio::spawn(async move {
+        let code = tokio::select! {
+            _ = hangup.recv() => libc::SIGHUP,
+            _ = terminate.recv() => libc::SIGTERM,
+        };
+
+        purge_manifest(&manifest);
+        let _ = disable_raw_mode();
+        let _ = execute!(io::stdout(), LeaveAlternateScreen, DisableMouseCapture);
+        process::exit(128 + code);
+    });
+}
+
+#[cfg(not(unix))]
+fn purge_on_signal(_manifest: PathBuf) {}
+
+pub async fn start_ui(config: Config) -> Result<ExitSummary, io::Error> {
+    if let (Some(manifest), false) = (config.quarantine_manifest(), config.dry_run) {
+        // Sessions that were killed left their quarantine behind.
+        let leftovers = manifest.clone();
+        tokio::task::spawn_blocking(move || purge_leftovers(&leftovers));
+        purge_on_panic(manifest.clone());
+        purge_on_signal(manifest);
+    }
+
     enable_raw_mode().expect("Error");
     let mut stdout = io::stdout();
     execute!(stdout, EnterAlternateScreen, EnableMouseCapture).expect("Error");
@@ -239,50 +1176,81 @@ pub async fn start_ui() -> Result<String, io::Error> {
 
     // ② Create app
 
-    let app = Arc::new(tokio::sync::Mutex::new(App::new(sync_io_tx.clone())));
+    let app = App::new(sync_io_tx.clone(), config.worker_pool(), config.search_option(), &config);
+    let app = Arc::new(tokio::sync::Mutex::new(app));
     let app_ui = Arc::clone(&app);
     let mut is_initialize = false;
 
-    let now = Instant::now();
-
     // ④ Handle I/O
     tokio::spawn(async move {
         let mut handler = IoAsyncHandler::new(app);
         while let Some(io_event) = sync_io_rx.recv().await {
-            handler.handle_io_event(io_event, now).await;
+            handler.handle_io_event(io_event).await;
         }
     });
 
-    let free_space = loop {
+    loop {
         let mut app = app_ui.lock().await;
 
         // Render
-        match terminal.draw(|rect| drawn(rect, &mut app)) {
-            Err(_) => break app.free_space,
-            _ => {}
+        if terminal.draw(|rect| drawn(rect, &mut app)).is_err() {
+            break;
         }
 
         if !is_initialize {
-           if let Err(_) = sync_io_tx.send(IoEventType::Initialize).await {
-               break app.free_space;
+           if sync_io_tx.send(IoEventType::Initialize).await.is_err() {
+               break;
            }
            is_initialize = true;
         }
 
-        let event = match events.next() {
-            Err(_) => break app.free_space,
+        // Waiting for a key without the app lets the I/O handler apply events as they come,
+        // rather than one per tick.
+        drop(app);
+        let event = match tokio::task::block_in_place(|| events.next()) {
+            Err(_) => break,
             Ok(event) => event,
         };
+        let mut app = app_ui.lock().await;
+
+        // A pending deletion takes the next key: confirm it or drop it.
+        if app.pending_delete.is_some() {
+            match event {
+                InputEventType::Tick => {},
+                InputEventType::Confirm => app.confirm_delete(),
+                _ => app.cancel_delete(),
+            }
+            continue;
+        }
 
         // ② Handle inputs
         match event {
-            InputEventType::Quit => break app.free_space,
-            InputEventType::Up => app.previous(),
-            InputEventType::Down => app.next(),
-            InputEventType::Select => app.delete_file(),
-            InputEventType::Tick => continue,
+            InputEventType::Quit => break,
+            InputEventType::Up => app.up(),
+            InputEventType::Down => app.down(),
+            InputEventType::Select => app.select(),
+            InputEventType::Open => app.open(),
+            // Like deleting, marking acts on the table the drill-down view hides.
+            InputEventType::Mark | InputEventType::MarkAll | InputEventType::InvertMarks | InputEventType::MarkIdle | InputEventType::DeleteMarked if !app.browser.is_empty() => continue,
+            InputEventType::Mark => app.toggle_mark(),
+            InputEventType::MarkAll => app.mark_all(),
+            InputEventType::InvertMarks => app.invert_marks(),
+            InputEventType::MarkIdle => app.mark_idle(),
+            InputEventType::DeleteMarked => app.delete_marked(),
+            InputEventType::Back => app.back(),
+            InputEventType::Rescan => app.rescan(),
+            InputEventType::AgeFilter => app.toggle_age_filter(),
+            InputEventType::SizeMode => app.toggle_size_mode(),
+            InputEventType::Undo => app.undo_delete(),
+            InputEventType::Confirm | InputEventType::Cancel | InputEventType::Tick => continue,
         }
-    };
+    }
+
+    // Stop outstanding searches and size calculations before leaving.
+    let mut app = app_ui.lock().await;
+    app.scan.cancel.cancel();
+    app.close_browser();
+    app.save_cache();
 
     // restore terminal
     disable_raw_mode().expect("Error");
@@ -291,9 +1259,20 @@ pub async fn start_ui() -> Result<String, io::Error> {
         LeaveAlternateScreen,
         DisableMouseCapture
     ).expect("Error");
+
+    // Deletions are only final now, which can take a while for large directories.
+    app.purge_quarantine();
     terminal.show_cursor()?;
+    let free_space = app.free_space;
+    let mut errors = std::mem::take(&mut app.past_errors);
+    errors.extend(app.scan.errors.all());
+    // A rescan runs into the same errors again.
+    let mut seen = HashSet::new();
+    errors.retain(|error| seen.insert((error.path.clone(), error.kind)));
+    let (trash, dry_run) = (app.trash, app.dry_run);
+    drop(app);
 
-    Ok(size(free_space))
+    Ok(ExitSummary { free_space, trash, dry_run, errors })
 }
 
 fn drawn<B: Backend>(rect: &mut Frame<B>, app: &mut App) {
@@ -303,7 +1282,7 @@ fn drawn<B: Backend>(rect: &mut Frame<B>, app: &mut App) {
 
     let chunks = Layout::default()
         .direction(Direction::Vertical)
-        .constraints([Constraint::Length(7), Constraint::Min(7)].as_ref())
+        .constraints([Constraint::Length(TITLE_HEIGHT), Constraint::Min(7)].as_ref())
         .split(size);
 
     let title = title();
@@ -311,15 +1290,15 @@ fn drawn<B: Backend>(rect: &mut Frame<B>, app: &mut App) {
 
     let version_chunk = Layout::default()
     .direction(Direction::Vertical)
-    .constraints([Constraint::Length(2), Constraint::Min(2)].as_ref())
+    .constraints([Constraint::Length(VERSION_HEIGHT), Constraint::Min(2)].as_ref())
     .split(chunks[1]);
 
-    let version = version_block();
+    let version = version_block(app.dry_run);
     rect.render_widget(version, version_chunk[0]);
 
     let guideline_chunk = Layout::default()
     .direction(Direction::Vertical)
-    .constraints([Constraint::Length(3), Constraint::Min(3)].as_ref())
+    .constraints([Constraint::Length(guideline_height()), Constraint::Min(3)].as_ref())
     .split(version_chunk[1]);
 
     let guideline = guideline();
@@ -327,29 +1306,66 @@ fn drawn<B: Backend>(rect: &mut Frame<B>, app: &mut App) {
 
     let mid_chunk = Layout::default()
     .direction(Direction::Vertical)
-    .constraints([Constraint::Length(3), Constraint::Min(3)].as_ref())
+    .constraints([Constraint::Length(STATUS_HEIGHT), Constraint::Min(3)].as_ref())
     .split(guideline_chunk[1]);
 
-    let status_block = status_block(app.total_size, app.time_init, app.free_space);
-    rect.render_widget(status_block, mid_chunk[0]);
+    let info_chunk = Layout::default()
+    .direction(Direction::Horizontal)
+    .constraints([Constraint::Percentage(50), Constraint::Percentage(50)].as_ref())
+    .split(mid_chunk[0]);
+
+    let status_block = status_block(app.status_info());
+    rect.render_widget(status_block, info_chunk[0]);
+
+    // The log gives way to the table on short terminals.
+    let log_height = ERROR_LOG_LINES as u16 + 2;
+    let show_log = !app.scan.errors.is_empty() && mid_chunk[1].height >= MIN_TABLE_HEIGHT + log_height;
+    let table_chunk = match show_log {
+        false => vec![mid_chunk[1]],
+        true => Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(MIN_TABLE_HEIGHT), Constraint::Length(log_height)].as_ref())
+            .split(mid_chunk[1]),
+    };
+
+    if let Some(log_chunk) = table_chunk.get(1) {
+        let error_log = error_log_block(&app.scan.errors.latest(ERROR_LOG_LINES), app.scan.errors.len());
+        rect.render_widget(error_log, *log_chunk);
+    }
 
-    match &app.data {
-        Some(data) => {
-            let table = table(data);
-            rect.render_stateful_widget(table, mid_chunk[1], &mut app.state);
-        },
-        None => {
-            let placeholder = table_placeholder();
-            rect.render_widget(placeholder, mid_chunk[1]);
+    app.sync_selection();
+    let selected = app.selected_index().and_then(|index| app.data.as_ref().map(|data| &data[index]));
+    rect.render_widget(details_block(selected), info_chunk[1]);
+
+    if let Some(level) = app.browser.last_mut() {
+        let browser = browser_table(level, app.size_mode);
+        rect.render_stateful_widget(browser, table_chunk[0], &mut level.state);
+    } else {
+        match &app.data {
+            Some(_) => {
+                let table = table(&app.visible_data(), app.size_mode);
+                rect.render_stateful_widget(table, table_chunk[0], &mut app.state);
+            },
+            None => {
+                let placeholder = table_placeholder();
+                rect.render_widget(placeholder, table_chunk[0]);
+            }
         }
     }
+
+    if let Some((paths, total_size)) = app.pending_summary() {
+        let area = popup_area(table_chunk[0], 70, confirm_lines(&paths));
+        rect.render_widget(Clear, area);
+        rect.render_widget(confirm_popup(&paths, total_size, app.trash && !app.dry_run), area);
+    }
 }
 
 fn check_size(rect: &Rect) {
     if rect.width < 52 {
         panic!("Require width >= 52, (got {})", rect.width);
     }
-    if rect.height < 28 {
-        panic!("Require height >= 28, (got {})", rect.height);
+    let min_height = TITLE_HEIGHT + VERSION_HEIGHT + guideline_height() + STATUS_HEIGHT + MIN_TABLE_HEIGHT;
+    if rect.height < min_height {
+        panic!("Require height >= {}, (got {})", min_height, rect.height);
     }
 }
\ No newline at end of file
@@ -3,7 +3,9 @@ use std::time::Duration;
 use humantime::format_duration;
 use tui::{widgets::{Paragraph, Wrap}, text::{Spans, Span}, style::{Style, Color, Modifier}, layout::Alignment};
 
-use crate::file_helper::size;
+use crate::file_helper::{size, duration};
+
+use super::SizeMode;
 
 fn info<'a>(field_name: String, value: String) -> Spans<'a> {
     Spans::from(vec![
@@ -13,23 +15,65 @@ fn info<'a>(field_name: String, value: String) -> Spans<'a> {
     ])
 }
 
-pub fn status_block<'a>(total_size: Option<u128>, time_init: Option<Duration>, free_space: u128) -> Paragraph<'a> {
-    let total_size_value = match total_size {
-        None => "..".to_owned(),
-        Some(byte) => size(byte)
+fn search_info<'a>(searching: bool, watching: bool) -> Spans<'a> {
+    let (value, color) = match (searching, watching) {
+        (true, _) => ("Searching..", Color::Yellow),
+        (false, true) => ("Done, watching for changes", Color::Cyan),
+        (false, false) => ("Done", Color::Green),
+    };
+
+    Spans::from(vec![
+        Span::raw("Search: "),
+        Span::styled(value, Style::default().fg(color).add_modifier(Modifier::BOLD))
+    ])
+}
+
+/// Everything shown in the status block.
+pub struct StatusInfo {
+    pub total_size: Option<u128>,
+    /// The total still grows while directories are being measured.
+    pub provisional: bool,
+    pub size_mode: SizeMode,
+    pub time_init: Option<Duration>,
+    pub free_space: u128,
+    /// Deleted directories go to the trash, so nothing is freed yet.
+    pub trash: bool,
+    /// Number and combined size of the marked rows.
+    pub marked: (usize, u128),
+    pub searching: bool,
+    pub watching: bool,
+    pub age_filter: Option<Duration>,
+}
+
+pub fn status_block<'a>(status: StatusInfo) -> Paragraph<'a> {
+    let total_size_value = match (status.total_size, status.provisional) {
+        (None, _) => "..".to_owned(),
+        (Some(byte), true) => format!("{}+", size(byte)),
+        (Some(byte), false) => size(byte),
     };
 
-    let duration_value = match time_init {
+    let duration_value = match status.time_init {
         None => "..".to_owned(),
         Some(dur) => format_duration(dur).to_string(),
     };
 
-    let free_space_value = size(free_space);
+    let free_space_value = size(status.free_space);
+
+    let age_filter_value = match status.age_filter {
+        None => "off".to_owned(),
+        Some(threshold) => format!("idle for {} or more", duration(threshold)),
+    };
 
     let info_block = vec![
-        info("Total size".to_owned(), total_size_value),
+        info(format!("Total size ({})", status.size_mode.name()), total_size_value),
         info("Time".to_owned(), duration_value),
-        info("Free space".to_owned(), free_space_value)
+        match status.trash {
+            true => info("Moved to trash".to_owned(), free_space_value),
+            false => info("Free space".to_owned(), free_space_value),
+        },
+        info("Marked".to_owned(), format!("{} ({})", status.marked.0, size(status.marked.1))),
+        search_info(status.searching, status.watching),
+        info("Filter".to_owned(), age_filter_value),
     ];
     Paragraph::new(info_block)
         .style(Style::default().bg(Color::Black))
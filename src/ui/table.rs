@@ -1,18 +1,29 @@
 use tui::{widgets::{Table, Cell, Row, Block, Borders}, style::{Style, Color, Modifier}, layout::Constraint};
 
-use crate::file_helper::size;
+use crate::file_helper::{size, age};
 
-use super::{DirData, DirStatus};
+use super::{DirData, DirStatus, SizeMode};
 
 const ROW_BOTTOM_MARGIN: u16 = 1u16;
 
-fn get_status_cell<'a>(status: &DirStatus) -> Cell<'a> {
+fn get_status_cell<'a>(item: &DirData) -> Cell<'a> {
+    if item.is_sizing() {
+        return Cell::from("LOADING");
+    }
+
+    if item.status == DirStatus::Ready && item.is_partial() {
+        return Cell::from("PARTIAL").style(Style::default().fg(Color::Yellow));
+    }
+
+    let status = &item.status;
     let content = match status {
         DirStatus::Loading => "LOADING".to_owned(),
         DirStatus::Ready => "READY".to_owned(),
         DirStatus::Deleting => "DELETING".to_owned(),
         DirStatus::Deleted => "DELETED".to_owned(),
+        DirStatus::Quarantined => "QUARANTINED".to_owned(),
         DirStatus::Error => "ERROR".to_owned(),
+        DirStatus::Removed => "REMOVED".to_owned(),
     };
 
     let mut cell = Cell::from(content);
@@ -27,38 +38,79 @@ fn get_status_cell<'a>(status: &DirStatus) -> Cell<'a> {
         DirStatus::Deleted => {
             cell = cell.style(Style::default().fg(Color::Green).bg(Color::White));
         },
+        DirStatus::Quarantined => {
+            cell = cell.style(Style::default().fg(Color::Yellow).bg(Color::White));
+        },
         DirStatus::Error => {
             cell = cell.style(Style::default().fg(Color::Red));
         },
+        DirStatus::Removed => {
+            cell = cell.style(Style::default().fg(Color::DarkGray));
+        },
         _ => {}
     };
 
     cell
 }
 
-pub fn table<'a>(items: &Vec<DirData>) -> Table<'a> {
+/// Symlinked targets are listed apart: deleting them only removes the link.
+fn get_profile_cell<'a>(item: &DirData) -> Cell<'a> {
+    match item.is_link {
+        true => Cell::from(format!("{} (link)", item.profile)).style(Style::default().fg(Color::Magenta)),
+        false => Cell::from(item.profile.clone()),
+    }
+}
+
+pub fn table<'a>(items: &[&DirData], size_mode: SizeMode) -> Table<'a> {
     let rows: Vec<Row> = items.iter().map(|item| {
         let cells = vec![
-            Cell::from(item.path.clone()),
-            match item.size {
-                Some(byte) => Cell::from(size(byte)),
+            match item.marked {
+                true => Cell::from("[x]").style(Style::default().fg(Color::Yellow)),
+                false => Cell::from("[ ]"),
+            },
+            Cell::from(item.root.clone()),
+            Cell::from(item.display_path().to_owned()),
+            get_profile_cell(item),
+            match (item.last_activity, item.activity_loaded) {
+                (Some(time), _) => Cell::from(age(time)),
+                (None, true) => Cell::from("-"),
+                (None, false) => Cell::from(".."),
+            },
+            match &item.size {
+                Some(dir_size) if item.is_provisional() => Cell::from(format!("{}+", size(size_mode.of(dir_size)))).style(Style::default().fg(Color::Yellow)),
+                Some(dir_size) if item.revalidating => Cell::from(size(size_mode.of(dir_size))).style(Style::default().fg(Color::DarkGray)),
+                Some(dir_size) => Cell::from(size(size_mode.of(dir_size))),
+                None => Cell::from(".."),
+            },
+            match &item.size {
+                Some(stats) => Cell::from(stats.files.to_string()),
+                None => Cell::from(".."),
+            },
+            match &item.size {
+                Some(stats) => Cell::from(stats.packages.to_string()),
                 None => Cell::from(".."),
             },
-            get_status_cell(&item.status)
+            get_status_cell(item)
         ];
         Row::new(cells).bottom_margin(ROW_BOTTOM_MARGIN)
     }).collect();
 
     Table::new(rows)
-        .header(Row::new(vec!["Path", "Size", "Status"])
+        .header(Row::new(vec!["", "Root", "Path", "Profile", "Age", size_mode.label(), "Files", "Pkgs", "Status"])
             .style(Style::default().fg(Color::Cyan))
             .bottom_margin(ROW_BOTTOM_MARGIN)
         )
         .block(Block::default().borders(Borders::ALL))
         .highlight_style(Style::default().add_modifier(Modifier::REVERSED))
         .widths(&[
-            Constraint::Percentage(70),
-            Constraint::Percentage(20),
+            Constraint::Length(3),
+            Constraint::Percentage(13),
+            Constraint::Percentage(26),
             Constraint::Percentage(10),
+            Constraint::Percentage(9),
+            Constraint::Percentage(12),
+            Constraint::Percentage(8),
+            Constraint::Percentage(7),
+            Constraint::Percentage(12),
         ])
 }
\ No newline at end of file
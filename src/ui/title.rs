@@ -1,6 +1,6 @@
 use tui::{widgets::Paragraph, layout::Alignment, style::{Style, Color}};
 
-const TITLE: &'static str = r"
+const TITLE: &str = r"
                        __                           __   .__.__  .__   
 _______ __ __  _______/  |_            ____ ______ |  | _|__|  | |  |  
 \_  __ \  |  \/  ___/\   __\  ______  /    \\____ \|  |/ /  |  | |  |  
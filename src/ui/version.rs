@@ -1,8 +1,14 @@
-use tui::{widgets::{Paragraph, Wrap}, style::{Style, Color}, layout::Alignment};
+use tui::{widgets::{Paragraph, Wrap}, text::{Spans, Span}, style::{Style, Color, Modifier}, layout::Alignment};
 
-pub fn version_block<'a>() -> Paragraph<'a> {
+pub fn version_block<'a>(dry_run: bool) -> Paragraph<'a> {
     const VERSION: &str = env!("CARGO_PKG_VERSION");
-    Paragraph::new(VERSION)
+
+    let mut lines = vec![Spans::from(VERSION)];
+    if dry_run {
+        lines.push(Spans::from(Span::styled("DRY RUN - nothing is deleted", Style::default().fg(Color::Red).add_modifier(Modifier::BOLD))));
+    }
+
+    Paragraph::new(lines)
         .style(Style::default().fg(Color::LightBlue))
         .alignment(Alignment::Center)
         .wrap(Wrap { trim: true })